@@ -23,6 +23,14 @@ async fn main() -> Result<()> {
 
 async fn general(speaker: &Speaker) -> Result<()> {
     println!("Name: {}", speaker.name());
+
+    let zone_info = speaker.zone_info().await?;
+    println!(
+        "Serial: {} (software {}, IP {})",
+        zone_info.serial_number(),
+        zone_info.display_software_version(),
+        zone_info.ip_address()
+    );
     Ok(())
 }
 
@@ -67,11 +75,11 @@ async fn equalizer(_speaker: &Speaker) -> Result<()> {
 }
 
 async fn group_state(speaker: &Speaker) -> Result<()> {
-    let groups: Vec<_> = speaker
-        .zone_group_state()
-        .await?
-        .into_iter()
-        .filter(|(_, speakers)| speakers.len() > 1)
+    let state = speaker.zone_group_state().await?;
+    let groups: Vec<_> = state
+        .groups()
+        .iter()
+        .filter(|group| group.members().iter().filter(|m| !m.is_invisible()).count() > 1)
         .collect();
 
     if groups.is_empty() {
@@ -80,20 +88,43 @@ async fn group_state(speaker: &Speaker) -> Result<()> {
 
     println!();
     println!("Groups: ");
-    for (coordinator, speakers) in groups {
-        let coordinator = speakers
+    for group in groups {
+        let coordinator = group
+            .members()
             .iter()
-            .find(|s| s.uuid().eq_ignore_ascii_case(&coordinator))
+            .find(|m| m.info().uuid().eq_ignore_ascii_case(group.coordinator()))
             .expect("no coordinator for group");
 
-        println!(" - {}", coordinator.name());
-        for speaker in speakers {
-            println!("   - {} : {}", speaker.name(), speaker.uuid());
+        println!(" - {}", coordinator.info().name());
+        for member in group.members() {
+            let speaker = member.info();
+            match speaker.channels() {
+                Some(channels) => println!(
+                    "   - {} : {} ({})",
+                    speaker.name(),
+                    speaker.uuid(),
+                    channels.join(",")
+                ),
+                None => println!("   - {} : {}", speaker.name(), speaker.uuid()),
+            }
+            for satellite in member.satellites() {
+                println!(
+                    "     - {} ({})",
+                    satellite.uuid(),
+                    satellite.channels().unwrap_or_default().join(",")
+                );
+            }
         }
     }
     Ok(())
 }
 
-fn fmt_duration(secs: u32) -> String {
-    return format!("{:02}:{:02}", secs / 60, secs % 60);
+fn fmt_duration(duration: Option<Duration>) -> String {
+    match duration {
+        Some(duration) => {
+            let secs = duration.as_secs();
+            format!("{:02}:{:02}", secs / 60, secs % 60)
+        }
+        None => "--:--".to_string(),
+    }
 }
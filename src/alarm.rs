@@ -0,0 +1,296 @@
+use crate::{
+    args,
+    content::Content,
+    datatypes::{self, RepeatMode},
+    manager::escape::escape_str_pcdata,
+    utils, Error, Result,
+};
+use roxmltree::{Document, Node};
+use std::{fmt, str::FromStr, time::Duration};
+
+/// Describes on which days an [Alarm](struct.Alarm.html) goes off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recurrence {
+    /// Only the next time the start time comes around.
+    Once,
+    /// Monday to Friday.
+    Weekdays,
+    /// Saturday and Sunday.
+    Weekends,
+    /// Every day.
+    Daily,
+    /// On the given days of the week, where `0` is Sunday and `6` is Saturday.
+    On(Vec<u8>),
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Once => f.write_str("ONCE"),
+            Recurrence::Weekdays => f.write_str("WEEKDAYS"),
+            Recurrence::Weekends => f.write_str("WEEKENDS"),
+            Recurrence::Daily => f.write_str("DAILY"),
+            Recurrence::On(days) => {
+                f.write_str("ON_")?;
+                days.iter().try_for_each(|day| write!(f, "{}", day))
+            }
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_uppercase().as_str() {
+            "ONCE" => Ok(Recurrence::Once),
+            "WEEKDAYS" => Ok(Recurrence::Weekdays),
+            "WEEKENDS" => Ok(Recurrence::Weekends),
+            "DAILY" => Ok(Recurrence::Daily),
+            s if s.starts_with("ON_") => s[3..]
+                .chars()
+                .map(|c| match c.to_digit(10) {
+                    Some(day) if day < 7 => Ok(day as u8),
+                    _ => Err(rupnp::Error::ParseError("invalid day in alarm recurrence").into()),
+                })
+                .collect::<Result<_>>()
+                .map(Recurrence::On),
+            _ => Err(rupnp::Error::ParseError("unknown alarm recurrence").into()),
+        }
+    }
+}
+
+/// An alarm of the AlarmClock service.
+/// Alarms are shared by the whole household, so they can be listed and edited through any speaker.
+/// Existing alarms are obtained by [Speaker::list_alarms](struct.Speaker.html#method.list_alarms),
+/// new ones are created with [Alarm::new](struct.Alarm.html#method.new) and
+/// [Speaker::create_alarm](struct.Speaker.html#method.create_alarm).
+#[derive(Debug, Clone)]
+pub struct Alarm {
+    id: Option<u32>,
+    start_time: Duration,
+    duration: Duration,
+    recurrence: Recurrence,
+    enabled: bool,
+    room_uuid: String,
+    program_uri: String,
+    program_metadata: String,
+    repeat_mode: RepeatMode,
+    shuffle: bool,
+    volume: u32,
+    include_linked_zones: bool,
+}
+
+#[allow(missing_docs)]
+impl Alarm {
+    /// Creates an enabled daily alarm in the given room which plays the Sonos chime for one hour.
+    /// `start_time` is the local time of day, counted from midnight.
+    pub fn new(room_uuid: impl Into<String>, start_time: Duration) -> Self {
+        Self {
+            id: None,
+            start_time,
+            duration: Duration::from_secs(3600),
+            recurrence: Recurrence::Daily,
+            enabled: true,
+            room_uuid: room_uuid.into(),
+            program_uri: "x-rincon-buzzer:0".to_string(),
+            program_metadata: String::new(),
+            repeat_mode: RepeatMode::None,
+            shuffle: false,
+            volume: 20,
+            include_linked_zones: false,
+        }
+    }
+
+    pub(crate) fn from_xml(node: Node<'_, '_>) -> Result<Self> {
+        let attribute = |name| utils::find_node_attribute(node, name);
+        let parse_u32 = |name| -> Result<u32> {
+            attribute(name)?
+                .parse()
+                .map_err(|e| rupnp::Error::invalid_response(e).into())
+        };
+        let (repeat_mode, shuffle) = datatypes::parse_play_mode(attribute("PlayMode")?)?;
+
+        Ok(Self {
+            id: Some(parse_u32("ID")?),
+            start_time: Duration::from_secs(
+                utils::seconds_from_str(attribute("StartTime")?)?.into(),
+            ),
+            duration: Duration::from_secs(utils::seconds_from_str(attribute("Duration")?)?.into()),
+            recurrence: attribute("Recurrence")?.parse()?,
+            enabled: utils::parse_bool(attribute("Enabled")?.to_string())?,
+            room_uuid: attribute("RoomUUID")?.to_string(),
+            program_uri: attribute("ProgramURI")?.to_string(),
+            program_metadata: attribute("ProgramMetaData")?.to_string(),
+            repeat_mode,
+            shuffle,
+            volume: parse_u32("Volume")?,
+            include_linked_zones: utils::parse_bool(attribute("IncludeLinkedZones")?.to_string())?,
+        })
+    }
+
+    /// The arguments shared by `CreateAlarm` and `UpdateAlarm`.
+    pub(crate) fn args(&self) -> String {
+        args! {
+            "StartLocalTime": utils::seconds_to_str(self.start_time.as_secs() as i64),
+            "Duration": utils::seconds_to_str(self.duration.as_secs() as i64),
+            "Recurrence": self.recurrence,
+            "Enabled": self.enabled as u8,
+            "RoomUUID": self.room_uuid,
+            "ProgramURI": escape_str_pcdata(&self.program_uri),
+            "ProgramMetaData": escape_str_pcdata(&self.program_metadata),
+            "PlayMode": datatypes::play_mode_str(self.repeat_mode, self.shuffle),
+            "Volume": self.volume,
+            "IncludeLinkedZones": self.include_linked_zones as u8
+        }
+        .to_string()
+    }
+
+    /// The ID assigned by the speaker. `None` if the alarm hasn't been created yet.
+    pub fn id(&self) -> Option<u32> {
+        self.id
+    }
+    pub fn start_time(&self) -> Duration {
+        self.start_time
+    }
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+    pub fn recurrence(&self) -> &Recurrence {
+        &self.recurrence
+    }
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+    pub fn room_uuid(&self) -> &str {
+        &self.room_uuid
+    }
+    pub fn program_uri(&self) -> &str {
+        &self.program_uri
+    }
+    pub fn program_metadata(&self) -> &str {
+        &self.program_metadata
+    }
+    /// The content the alarm plays, parsed from the DIDL program metadata.
+    /// Returns `None` for alarms without metadata, like the Sonos chime.
+    pub fn program(&self) -> Result<Option<Content>> {
+        Content::from_didl(&self.program_metadata)
+    }
+    pub fn repeat_mode(&self) -> RepeatMode {
+        self.repeat_mode
+    }
+    pub fn shuffle(&self) -> bool {
+        self.shuffle
+    }
+    pub fn volume(&self) -> u32 {
+        self.volume
+    }
+    pub fn include_linked_zones(&self) -> bool {
+        self.include_linked_zones
+    }
+
+    /// Sets the local time of day, counted from midnight
+    pub fn set_start_time(&mut self, start_time: Duration) -> &mut Self {
+        self.start_time = start_time;
+        self
+    }
+    /// Sets for how long the alarm plays
+    pub fn set_duration(&mut self, duration: Duration) -> &mut Self {
+        self.duration = duration;
+        self
+    }
+    pub fn set_recurrence(&mut self, recurrence: Recurrence) -> &mut Self {
+        self.recurrence = recurrence;
+        self
+    }
+    pub fn set_enabled(&mut self, enabled: bool) -> &mut Self {
+        self.enabled = enabled;
+        self
+    }
+    pub fn set_room_uuid(&mut self, room_uuid: impl Into<String>) -> &mut Self {
+        self.room_uuid = room_uuid.into();
+        self
+    }
+    /// Sets what the alarm plays. The metadata is the unescaped DIDL-Lite document, or an empty
+    /// string.
+    pub fn set_program(
+        &mut self,
+        uri: impl Into<String>,
+        metadata: impl Into<String>,
+    ) -> &mut Self {
+        self.program_uri = uri.into();
+        self.program_metadata = metadata.into();
+        self
+    }
+    pub fn set_play_mode(&mut self, repeat_mode: RepeatMode, shuffle: bool) -> &mut Self {
+        self.repeat_mode = repeat_mode;
+        self.shuffle = shuffle;
+        self
+    }
+    pub fn set_volume(&mut self, volume: u32) -> &mut Self {
+        self.volume = volume;
+        self
+    }
+    pub fn set_include_linked_zones(&mut self, include_linked_zones: bool) -> &mut Self {
+        self.include_linked_zones = include_linked_zones;
+        self
+    }
+}
+
+pub(crate) fn extract_alarm_list(alarm_list_xml: &str) -> Result<Vec<Alarm>> {
+    let doc = Document::parse(alarm_list_xml)?;
+    let alarms = utils::find_root_node(&doc, "Alarms", "Alarm List")?;
+
+    alarms
+        .children()
+        .filter(Node::is_element)
+        .filter(|c| c.tag_name().name().eq_ignore_ascii_case("Alarm"))
+        .map(Alarm::from_xml)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALARM_LIST: &str = r#"<Alarms><Alarm ID="14" StartTime="07:00:00" Duration="02:00:00" Recurrence="ON_12345" Enabled="1" RoomUUID="RINCON_000E58B0A3C001400" ProgramURI="x-rincon-buzzer:0" ProgramMetaData="" PlayMode="SHUFFLE_NOREPEAT" Volume="25" IncludeLinkedZones="0"/><Alarm ID="15" StartTime="22:30:00" Duration="00:30:00" Recurrence="DAILY" Enabled="0" RoomUUID="RINCON_000E58B0A3C001400" ProgramURI="x-sonosapi-stream:s17077?sid=254&amp;flags=8224&amp;sn=0" ProgramMetaData="&lt;DIDL-Lite xmlns:dc=&quot;http://purl.org/dc/elements/1.1/&quot; xmlns:upnp=&quot;urn:schemas-upnp-org:metadata-1-0/upnp/&quot; xmlns:r=&quot;urn:schemas-rinconnetworks-com:metadata-1-0/&quot; xmlns=&quot;urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/&quot;&gt;&lt;item id=&quot;F00092020s17077&quot; parentID=&quot;L&quot; restricted=&quot;true&quot;&gt;&lt;dc:title&gt;BBC Radio 4&lt;/dc:title&gt;&lt;upnp:class&gt;object.item.audioItem.audioBroadcast&lt;/upnp:class&gt;&lt;desc id=&quot;cdudn&quot; nameSpace=&quot;urn:schemas-rinconnetworks-com:metadata-1-0/&quot;&gt;SA_RINCON65031_&lt;/desc&gt;&lt;/item&gt;&lt;/DIDL-Lite&gt;" PlayMode="NORMAL" Volume="12" IncludeLinkedZones="1"/></Alarms>"#;
+
+    #[test]
+    fn test_extract_alarm_list() -> Result<()> {
+        let alarms = extract_alarm_list(ALARM_LIST)?;
+        assert_eq!(alarms.len(), 2);
+
+        let wake_up = &alarms[0];
+        assert_eq!(wake_up.id(), Some(14));
+        assert_eq!(wake_up.start_time(), Duration::from_secs(7 * 3600));
+        assert_eq!(wake_up.recurrence(), &Recurrence::On(vec![1, 2, 3, 4, 5]));
+        assert_eq!(
+            (wake_up.repeat_mode(), wake_up.shuffle()),
+            (RepeatMode::None, true)
+        );
+        assert!(wake_up.enabled());
+        assert!(wake_up.program()?.is_none());
+
+        let radio = &alarms[1];
+        assert_eq!(radio.duration(), Duration::from_secs(30 * 60));
+        assert_eq!(
+            radio.program_uri(),
+            "x-sonosapi-stream:s17077?sid=254&flags=8224&sn=0"
+        );
+        assert_eq!(
+            radio.program()?.expect("has metadata").title(),
+            "BBC Radio 4"
+        );
+        assert!(radio.include_linked_zones());
+        Ok(())
+    }
+
+    #[test]
+    fn test_recurrence_round_trip() -> Result<()> {
+        for recurrence in &["ONCE", "WEEKDAYS", "WEEKENDS", "DAILY", "ON_06"] {
+            assert_eq!(&recurrence.parse::<Recurrence>()?.to_string(), recurrence);
+        }
+        assert!("ON_7".parse::<Recurrence>().is_err());
+        Ok(())
+    }
+}
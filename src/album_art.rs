@@ -0,0 +1,201 @@
+use crate::{HttpError, Result, Uri};
+use hyper::{body::Bytes, Body, Response};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+const DEFAULT_MIME_TYPE: &str = "application/octet-stream";
+
+/// Album art as fetched by [fetch_album_art](struct.Speaker.html#method.fetch_album_art).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlbumArt {
+    data: Bytes,
+    mime_type: String,
+}
+
+impl AlbumArt {
+    /// The raw image data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+    /// The MIME type reported by the server, e.g. `image/jpeg`.
+    pub fn mime_type(&self) -> &str {
+        &self.mime_type
+    }
+}
+
+pub(crate) async fn fetch(uri: Uri) -> Result<AlbumArt> {
+    let response = get(uri).await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(rupnp::Error::HttpErrorCode(status).into());
+    }
+
+    let mime_type = response
+        .headers()
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or(DEFAULT_MIME_TYPE)
+        .to_string();
+    let data = hyper::body::to_bytes(response.into_body())
+        .await
+        .map_err(HttpError)?;
+
+    Ok(AlbumArt { data, mime_type })
+}
+
+#[cfg(feature = "https")]
+async fn get(uri: Uri) -> Result<Response<Body>> {
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+    let client = hyper::Client::builder().build::<_, Body>(connector);
+    Ok(client.get(uri).await.map_err(HttpError)?)
+}
+
+#[cfg(not(feature = "https"))]
+async fn get(uri: Uri) -> Result<Response<Body>> {
+    if uri.scheme() == Some(&http::uri::Scheme::HTTPS) {
+        return Err(crate::Error::HttpsNotEnabled(uri.to_string()));
+    }
+    Ok(hyper::Client::new().get(uri).await.map_err(HttpError)?)
+}
+
+/// An in-memory cache for album art, keyed by URI.
+/// Once the images take up more than `max_bytes`, the least recently used ones get evicted.
+/// The cache can be shared between tasks, e.g. behind an `Arc`.
+#[derive(Debug)]
+pub struct AlbumArtCache {
+    max_bytes: usize,
+    inner: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<String, AlbumArt>,
+    // least recently used first
+    order: VecDeque<String>,
+    size: usize,
+}
+
+impl AlbumArtCache {
+    /// Creates an empty cache holding at most `max_bytes` of image data.
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Returns the cached album art for `uri`, marking it as recently used.
+    pub fn get(&self, uri: &str) -> Option<AlbumArt> {
+        let mut state = self.inner.lock().unwrap();
+        let art = state.entries.get(uri)?.clone();
+        state.touch(uri);
+        Some(art)
+    }
+
+    /// Caches album art, evicting the least recently used entries if the cache gets too big.
+    /// Images larger than the whole cache are not stored.
+    pub fn insert(&self, uri: impl Into<String>, art: AlbumArt) {
+        if art.data.len() > self.max_bytes {
+            return;
+        }
+        let uri = uri.into();
+        let mut state = self.inner.lock().unwrap();
+
+        state.size += art.data.len();
+        if let Some(old) = state.entries.insert(uri.clone(), art) {
+            state.size -= old.data.len();
+            state.touch(&uri);
+        } else {
+            state.order.push_back(uri);
+        }
+
+        while state.size > self.max_bytes {
+            let oldest = match state.order.pop_front() {
+                Some(oldest) => oldest,
+                None => break,
+            };
+            if let Some(evicted) = state.entries.remove(&oldest) {
+                state.size -= evicted.data.len();
+            }
+        }
+    }
+
+    /// Removes all cached album art.
+    pub fn clear(&self) {
+        *self.inner.lock().unwrap() = CacheState::default();
+    }
+
+    /// The number of cached images.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The total size of the cached images in bytes.
+    pub fn size(&self) -> usize {
+        self.inner.lock().unwrap().size
+    }
+}
+
+impl CacheState {
+    fn touch(&mut self, uri: &str) {
+        if let Some(index) = self.order.iter().position(|u| u == uri) {
+            if let Some(uri) = self.order.remove(index) {
+                self.order.push_back(uri);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn art(len: usize) -> AlbumArt {
+        AlbumArt {
+            data: Bytes::from(vec![0; len]),
+            mime_type: "image/jpeg".to_string(),
+        }
+    }
+
+    #[cfg(not(feature = "https"))]
+    #[tokio::test]
+    async fn test_https_needs_feature() {
+        let uri = "https://i.scdn.co/image/ab67616d0000b273".parse().unwrap();
+        assert!(matches!(
+            fetch(uri).await,
+            Err(crate::Error::HttpsNotEnabled(_))
+        ));
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = AlbumArtCache::new(10);
+        cache.insert("a", art(4));
+        cache.insert("b", art(4));
+        assert!(cache.get("a").is_some());
+
+        cache.insert("c", art(4));
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+        assert_eq!(cache.size(), 8);
+
+        cache.insert("a", art(2));
+        assert_eq!(cache.size(), 6);
+
+        cache.insert("huge", art(11));
+        assert!(cache.get("huge").is_none());
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -1,10 +1,142 @@
-use crate::{Result};
-use roxmltree::Node;
+use crate::{
+    didl::{DidlLite, DidlObject},
+    utils::HashMapExt,
+    Result,
+};
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+
+/// A page of results of a browse request, along with the counts needed to fetch the next page.
+#[derive(Debug)]
+pub struct BrowseResult<T> {
+    pub(crate) items: Vec<T>,
+    pub(crate) number_returned: u32,
+    pub(crate) total_matches: u32,
+    pub(crate) update_id: u32,
+}
+
+impl<T> BrowseResult<T> {
+    pub(crate) fn from_map(
+        map: &mut HashMap<String, String>,
+        parse_item: impl Fn(Node<'_, '_>) -> Result<T>,
+    ) -> Result<Self> {
+        let mut number = |key| -> Result<u32> {
+            map.extract(key)?
+                .parse()
+                .map_err(|e| rupnp::Error::invalid_response(e).into())
+        };
+        let number_returned = number("NumberReturned")?;
+        let total_matches = number("TotalMatches")?;
+        let update_id = number("UpdateID")?;
+
+        let result = map.extract("Result")?;
+        let items = Document::parse(&result)?
+            .root()
+            .first_element_child()
+            .ok_or_else(|| rupnp::Error::ParseError("Browse Response contains no children"))?
+            .children()
+            .filter(Node::is_element)
+            .map(parse_item)
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            items,
+            number_returned,
+            total_matches,
+            update_id,
+        })
+    }
+
+    /// The items of this page.
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Consumes the result, returning the items of this page.
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+
+    /// The number of items in this page.
+    pub fn number_returned(&self) -> u32 {
+        self.number_returned
+    }
+
+    /// The number of items in the whole container.
+    pub fn total_matches(&self) -> u32 {
+        self.total_matches
+    }
+
+    /// The update id of the container, which changes whenever the container is modified.
+    pub fn update_id(&self) -> u32 {
+        self.update_id
+    }
+}
+
+/// The categories of the local music library that can be searched with
+/// [search_library](struct.Speaker.html#method.search_library).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LibraryCategory {
+    /// Contributing artists.
+    Artists,
+    /// Album artists.
+    AlbumArtists,
+    /// Albums.
+    Albums,
+    /// Genres.
+    Genres,
+    /// Composers.
+    Composers,
+    /// Tracks.
+    Tracks,
+    /// The music shares the library is indexed from.
+    Shares,
+}
+
+impl LibraryCategory {
+    /// The content directory object id of the category, e.g. `A:ALBUM`.
+    pub fn object_id(self) -> &'static str {
+        match self {
+            LibraryCategory::Artists => "A:ARTIST",
+            LibraryCategory::AlbumArtists => "A:ALBUMARTIST",
+            LibraryCategory::Albums => "A:ALBUM",
+            LibraryCategory::Genres => "A:GENRE",
+            LibraryCategory::Composers => "A:COMPOSER",
+            LibraryCategory::Tracks => "A:TRACKS",
+            LibraryCategory::Shares => "S:",
+        }
+    }
+
+    /// The object id for the entries of the category starting with `prefix`,
+    /// e.g. `A:ALBUM:Abbey%20Road`. An empty prefix matches the whole category.
+    pub(crate) fn search_id(self, prefix: &str) -> String {
+        match (self, prefix) {
+            (_, "") => self.object_id().to_string(),
+            (LibraryCategory::Shares, prefix) => format!("S:{}", urlencoding::encode(prefix)),
+            (category, prefix) => {
+                format!("{}:{}", category.object_id(), urlencoding::encode(prefix))
+            }
+        }
+    }
+}
+
+/// Whether a [Content](struct.Content.html) entry is an item or a container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    /// A single piece of media, like a track.
+    Item,
+    /// A collection of further content, like an album or a folder.
+    Container,
+}
 
 /// The content struct contains items from the content directory service
 #[derive(Debug)]
 pub struct Content {
+    id: String,
+    parent_id: String,
+    kind: ContentKind,
     title: String,
+    class: Option<String>,
     creator: Option<String>,
     album_art_uri: Option<String>,
     uri: Option<String>,
@@ -14,42 +146,73 @@ pub struct Content {
 
 impl Content {
     pub(crate) fn from_xml(node: Node<'_, '_>) -> Result<Self> {
-        let mut title = None;
-        let mut creator = None;
-        let mut album_art_uri = None;
-        let mut uri = None;
-        let mut metadata = None;
-
-        for child in node.children() {
-            // log::debug!("{:?}", child.tag_name().name());
-            match child.tag_name().name() {
-                "title" => title = Some(child.text().unwrap_or_default().to_string()),
-                "creator" => creator = Some(child.text().unwrap_or_default().to_string()),
-                "albumArtURI" => album_art_uri = Some(child.text().unwrap_or_default().to_string()),
-                "res" => uri = Some(child.text().unwrap_or_default().to_string()),
-                "resMD" => metadata = Some(child.text().unwrap_or_default().to_string()),
-                _ => (),
-            }
-        }
+        Self::from_object(DidlObject::from_xml(node)?)
+    }
 
-        let title = title.ok_or_else(|| {
-            rupnp::Error::XmlMissingElement(node.tag_name().name().to_string(), "title".to_string())
+    pub(crate) fn from_object(object: DidlObject) -> Result<Self> {
+        let title = object.title().map(str::to_string).ok_or_else(|| {
+            rupnp::Error::XmlMissingElement(object.id().to_string(), "title".to_string())
         })?;
+        let property = |name| object.property(name).map(str::to_string);
 
         Ok(Self {
+            id: object.id().to_string(),
+            parent_id: object.parent_id().to_string(),
+            kind: object.kind(),
             title,
-            creator,
-            album_art_uri,
-            uri,
-            metadata
+            class: property("upnp:class"),
+            creator: property("dc:creator"),
+            album_art_uri: property("upnp:albumArtURI"),
+            uri: object.resources().first().map(|res| res.uri().to_string()),
+            metadata: property("r:resMD"),
         })
     }
 
+    /// Parses the first item or container of a DIDL-Lite document.
+    /// Returns `None` for empty metadata.
+    pub(crate) fn from_didl(metadata: &str) -> Result<Option<Self>> {
+        if metadata.is_empty() {
+            return Ok(None);
+        }
+        let object = metadata
+            .parse::<DidlLite>()?
+            .into_objects()
+            .into_iter()
+            .next()
+            .ok_or(rupnp::Error::ParseError("DIDL metadata contains no children"))?;
+        Self::from_object(object).map(Some)
+    }
+
+    /// Get a reference to the content's object id, e.g. `SQ:3` for a Sonos playlist.
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
     /// Get a reference to the content's title.
     pub fn title(&self) -> &str {
         self.title.as_str()
     }
 
+    /// Get a reference to the object id of the content's parent, e.g. `SQ:` for a Sonos playlist.
+    pub fn parent_id(&self) -> &str {
+        self.parent_id.as_str()
+    }
+
+    /// Whether the content is a playable item or a container of further content.
+    pub fn kind(&self) -> ContentKind {
+        self.kind
+    }
+
+    /// Whether the content is a container that can be browsed further.
+    pub fn is_container(&self) -> bool {
+        self.kind == ContentKind::Container
+    }
+
+    /// Get a reference to the content's UPnP class, e.g. `object.container.album.musicAlbum`.
+    pub fn class(&self) -> Option<&String> {
+        self.class.as_ref()
+    }
+
     /// Get a reference to the content's creator.
     pub fn creator(&self) -> Option<&String> {
         self.creator.as_ref()
@@ -70,3 +233,30 @@ impl Content {
         self.metadata.as_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_identity() -> Result<()> {
+        let didl = r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"><container id="A:ALBUM/Abbey%20Road" parentID="A:ALBUM" restricted="true"><dc:title>Abbey Road</dc:title><upnp:class>object.container.album.musicAlbum</upnp:class><res protocolInfo="x-rincon-playlist:*:*:*">x-rincon-playlist:RINCON_000E58A0B1C201400#A:ALBUM/Abbey%20Road</res></container></DIDL-Lite>"#;
+        let album = Content::from_didl(didl)?.expect("has an entry");
+        assert_eq!(album.id(), "A:ALBUM/Abbey%20Road");
+        assert_eq!(album.parent_id(), "A:ALBUM");
+        assert!(album.is_container());
+        assert_eq!(
+            album.class().map(String::as_str),
+            Some("object.container.album.musicAlbum")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_library_search_id() {
+        assert_eq!(LibraryCategory::Albums.search_id(""), "A:ALBUM");
+        assert_eq!(LibraryCategory::Albums.search_id("Abbey Road"), "A:ALBUM:Abbey%20Road");
+        assert_eq!(LibraryCategory::AlbumArtists.search_id("AC/DC"), "A:ALBUMARTIST:AC%2FDC");
+        assert_eq!(LibraryCategory::Shares.search_id("NAS"), "S:NAS");
+    }
+}
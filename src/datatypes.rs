@@ -1,13 +1,15 @@
-use crate::{Result, Speaker};
+use crate::{content::Content, topology::ChannelMap, Result, Speaker};
 use roxmltree::Node;
 use std::{
     cmp::PartialEq,
     fmt,
     hash::{Hash, Hasher},
+    net::Ipv4Addr,
+    time::Duration,
 };
 
 /// This enum describes how Sonos repeats the current playlist.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RepeatMode {
     /// The playlist doesn't get repeated.
     None,
@@ -56,13 +58,219 @@ impl std::str::FromStr for RepeatMode {
     }
 }
 
+/// Parses the `PlayMode` strings used by AVTransport and AlarmClock into a repeat mode and
+/// whether shuffle is on.
+pub(crate) fn parse_play_mode(play_mode: &str) -> Result<(RepeatMode, bool)> {
+    match play_mode.to_uppercase().as_str() {
+        "NORMAL" => Ok((RepeatMode::None, false)),
+        "REPEAT_ALL" => Ok((RepeatMode::All, false)),
+        "REPEAT_ONE" => Ok((RepeatMode::One, false)),
+        "SHUFFLE_NOREPEAT" => Ok((RepeatMode::None, true)),
+        "SHUFFLE" => Ok((RepeatMode::All, true)),
+        "SHUFFLE_REPEAT_ONE" => Ok((RepeatMode::One, true)),
+        _ => Err(rupnp::Error::invalid_response(ParseRepeatModeError).into()),
+    }
+}
+
+pub(crate) fn play_mode_str(repeat_mode: RepeatMode, shuffle: bool) -> &'static str {
+    match (repeat_mode, shuffle) {
+        (RepeatMode::None, false) => "NORMAL",
+        (RepeatMode::One, false) => "REPEAT_ONE",
+        (RepeatMode::All, false) => "REPEAT_ALL",
+        (RepeatMode::None, true) => "SHUFFLE_NOREPEAT",
+        (RepeatMode::One, true) => "SHUFFLE_REPEAT_ONE",
+        (RepeatMode::All, true) => "SHUFFLE",
+    }
+}
+
+/// The settings reachable through the `GetEQ`/`SetEQ` actions of the RenderingControl service.
+/// Most of them only exist on home theater speakers like the Beam, Arc or Playbar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqType {
+    /// Night sound, reduces the dynamic range. `0` or `1`.
+    NightMode,
+    /// Speech enhancement. `0` or `1`.
+    DialogLevel,
+    /// The level of the subwoofer, from `-15` to `15`.
+    SubGain,
+    /// Whether the subwoofer is enabled. `0` or `1`.
+    SubEnabled,
+    /// The level of the surround speakers for TV audio, from `-15` to `15`.
+    SurroundLevel,
+    /// Whether the surround speakers are enabled. `0` or `1`.
+    SurroundEnabled,
+    /// The level of the surround speakers for music, from `-15` to `15`.
+    MusicSurroundLevel,
+    /// The level of the height channels, from `-10` to `10`.
+    HeightChannelLevel,
+    /// How music is played on the surround speakers. `0` is ambient, `1` is full.
+    SurroundMode,
+}
+
+// The `EQType` names the speaker expects, which differ from the variant names for some of them
+impl fmt::Display for EqType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EqType::NightMode => "NightMode",
+            EqType::DialogLevel => "DialogLevel",
+            EqType::SubGain => "SubGain",
+            EqType::SubEnabled => "SubEnable",
+            EqType::SurroundLevel => "SurroundLevel",
+            EqType::SurroundEnabled => "SurroundEnable",
+            EqType::MusicSurroundLevel => "MusicSurroundLevel",
+            EqType::HeightChannelLevel => "HeightChannelLevel",
+            EqType::SurroundMode => "SurroundMode",
+        })
+    }
+}
+
+/// How music is played back on the surround speakers of a home theater setup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurroundMode {
+    /// The surround speakers add ambience at a lower level.
+    Ambient,
+    /// The surround speakers play at full range.
+    Full,
+}
+
+/// The state of the AV transport of a speaker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransportState {
+    /// Playback is stopped, e.g. at the end of the queue.
+    Stopped,
+    /// The speaker is playing.
+    Playing,
+    /// Playback is paused and can be resumed where it left off.
+    PausedPlayback,
+    /// The speaker is about to start playing, e.g. while buffering a stream.
+    Transitioning,
+    /// There is nothing to play.
+    NoMediaPresent,
+    /// A state this crate doesn't know about, e.g. `RECORDING` from the UPnP spec.
+    Other(String),
+}
+
+impl From<&str> for TransportState {
+    fn from(s: &str) -> Self {
+        match s.to_uppercase().as_str() {
+            "STOPPED" => TransportState::Stopped,
+            "PLAYING" => TransportState::Playing,
+            "PAUSED_PLAYBACK" => TransportState::PausedPlayback,
+            "TRANSITIONING" => TransportState::Transitioning,
+            "NO_MEDIA_PRESENT" => TransportState::NoMediaPresent,
+            _ => TransportState::Other(s.to_string()),
+        }
+    }
+}
+
+/// The transport state, status and speed of a speaker, as returned by
+/// [transport_info](struct.Speaker.html#method.transport_info).
+#[derive(Debug, Clone)]
+pub struct TransportInfo {
+    pub(crate) state: TransportState,
+    pub(crate) status: String,
+    pub(crate) speed: String,
+}
+
+impl TransportInfo {
+    /// The current transport state.
+    pub fn state(&self) -> &TransportState {
+        &self.state
+    }
+    /// `OK` or `ERROR_OCCURRED`.
+    pub fn status(&self) -> &str {
+        &self.status
+    }
+    /// The playback speed, which is always `1` for Sonos speakers.
+    pub fn speed(&self) -> &str {
+        &self.speed
+    }
+}
+
+/// What is loaded into the AV transport of a speaker, as returned by
+/// [media_info](struct.Speaker.html#method.media_info).
+#[derive(Debug, Clone)]
+pub struct MediaInfo {
+    pub(crate) nr_tracks: u32,
+    pub(crate) media_duration: Option<Duration>,
+    pub(crate) current_uri: Option<String>,
+    pub(crate) current_uri_metadata: String,
+    pub(crate) next_uri: Option<String>,
+    pub(crate) next_uri_metadata: String,
+    pub(crate) play_medium: String,
+    pub(crate) record_medium: String,
+}
+
+#[allow(missing_docs)]
+impl MediaInfo {
+    /// The number of tracks in the queue, or `1` for a stream.
+    pub fn nr_tracks(&self) -> u32 {
+        self.nr_tracks
+    }
+    /// The duration of the media, if the speaker reports one.
+    pub fn media_duration(&self) -> Option<Duration> {
+        self.media_duration
+    }
+    /// The transport URI, e.g. `x-rincon-queue:RINCON_000E58A0B1C201400#0` for the queue.
+    pub fn current_uri(&self) -> Option<&str> {
+        self.current_uri.as_deref()
+    }
+    pub fn current_uri_metadata(&self) -> &str {
+        &self.current_uri_metadata
+    }
+    /// The container that is playing, e.g. a radio station or a favorite, parsed from the
+    /// DIDL metadata of the current URI. Returns `None` if there is no metadata, like for the queue.
+    pub fn current_content(&self) -> Result<Option<Content>> {
+        Content::from_didl(&self.current_uri_metadata)
+    }
+    pub fn next_uri(&self) -> Option<&str> {
+        self.next_uri.as_deref()
+    }
+    pub fn next_uri_metadata(&self) -> &str {
+        &self.next_uri_metadata
+    }
+    /// E.g. `NETWORK`.
+    pub fn play_medium(&self) -> &str {
+        &self.play_medium
+    }
+    pub fn record_medium(&self) -> &str {
+        &self.record_medium
+    }
+}
+
+/// The outcome of adding tracks to the queue with
+/// [queue_many](struct.Speaker.html#method.queue_many).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnqueuedTracks {
+    pub(crate) first_track_no: u32,
+    pub(crate) num_tracks_added: u32,
+    pub(crate) new_queue_length: u32,
+}
+
+impl EnqueuedTracks {
+    /// The track number of the first added track, starting at 1.
+    pub fn first_track_no(&self) -> u32 {
+        self.first_track_no
+    }
+    /// The number of tracks that were added. Containers like albums add more than one track.
+    pub fn num_tracks_added(&self) -> u32 {
+        self.num_tracks_added
+    }
+    /// The length of the queue after adding the tracks.
+    pub fn new_queue_length(&self) -> u32 {
+        self.new_queue_length
+    }
+}
+
 /// A more lightweight representation of a speaker containing only the name, uuid and location.
-/// It gets returned by the [zone_group_state](struct.Speaker.html#method.zone_group_state) function.
+/// It is part of the [ZoneGroupState](struct.ZoneGroupState.html) returned by the [zone_group_state](struct.Speaker.html#method.zone_group_state) function.
 #[derive(Debug, Eq, Clone)]
 pub struct SpeakerInfo {
     pub(crate) name: String,
     pub(crate) uuid: String,
     pub(crate) location: String,
+    pub(crate) channel_map: Option<ChannelMap>,
+    pub(crate) ht_sat_chan_map: Option<ChannelMap>,
 }
 impl PartialEq for SpeakerInfo {
     fn eq(&self, other: &Self) -> bool {
@@ -81,12 +289,16 @@ impl SpeakerInfo {
         let mut uuid = None;
         let mut name = None;
         let mut location = None;
+        let mut channel_map = None;
+        let mut ht_sat_chan_map = None;
 
         for attr in node.attributes() {
             match attr.name().to_lowercase().as_str() {
                 "uuid" => uuid = Some(attr.value()),
                 "location" => location = Some(attr.value()),
                 "zonename" => name = Some(attr.value()),
+                "channelmapset" => channel_map = Some(attr.value()),
+                "htsatchanmapset" => ht_sat_chan_map = Some(attr.value()),
                 _ => (),
             }
         }
@@ -116,6 +328,8 @@ impl SpeakerInfo {
                     )
                 })?
                 .to_string(),
+            channel_map: channel_map.map(str::parse).transpose()?,
+            ht_sat_chan_map: ht_sat_chan_map.map(str::parse).transpose()?,
         })
     }
 
@@ -128,4 +342,109 @@ impl SpeakerInfo {
     pub fn location(&self) -> &str {
         &self.location
     }
+    /// The channel map of the bonded set (e.g. a stereo pair) this speaker is the visible part of.
+    pub fn channel_map(&self) -> Option<&ChannelMap> {
+        self.channel_map.as_ref()
+    }
+    /// The channel map of the home theater setup (soundbar, surrounds and sub) this speaker belongs to.
+    pub fn ht_sat_chan_map(&self) -> Option<&ChannelMap> {
+        self.ht_sat_chan_map.as_ref()
+    }
+    /// The channels this speaker plays in its bonded set, e.g. `["LR", "LR"]` for a left surround
+    /// or `["SW"]` for a sub. `None` if the speaker isn't bonded.
+    pub fn channels(&self) -> Option<&[String]> {
+        self.ht_sat_chan_map
+            .as_ref()
+            .and_then(|map| map.channels(&self.uuid))
+            .or_else(|| self.channel_map.as_ref()?.channels(&self.uuid))
+    }
+}
+
+/// Hardware and software information about a speaker, as returned by
+/// [zone_info](struct.Speaker.html#method.zone_info).
+#[derive(Debug, Clone)]
+pub struct ZoneInfo {
+    pub(crate) serial_number: String,
+    pub(crate) software_version: String,
+    pub(crate) display_software_version: String,
+    pub(crate) hardware_version: String,
+    pub(crate) ip_address: Ipv4Addr,
+    pub(crate) mac_address: String,
+    pub(crate) extra_info: String,
+}
+
+#[allow(missing_docs)]
+impl ZoneInfo {
+    pub fn serial_number(&self) -> &str {
+        &self.serial_number
+    }
+    pub fn software_version(&self) -> &str {
+        &self.software_version
+    }
+    /// The software version as shown in the Sonos app, e.g. `13.4`.
+    pub fn display_software_version(&self) -> &str {
+        &self.display_software_version
+    }
+    pub fn hardware_version(&self) -> &str {
+        &self.hardware_version
+    }
+    pub fn ip_address(&self) -> Ipv4Addr {
+        self.ip_address
+    }
+    pub fn mac_address(&self) -> &str {
+        &self.mac_address
+    }
+    pub fn extra_info(&self) -> &str {
+        &self.extra_info
+    }
+}
+
+/// The room name, icon and configuration of a speaker, as returned by
+/// [zone_attributes](struct.Speaker.html#method.zone_attributes).
+#[derive(Debug, Clone)]
+pub struct ZoneAttributes {
+    pub(crate) name: String,
+    pub(crate) icon: String,
+    pub(crate) configuration: String,
+}
+
+#[allow(missing_docs)]
+impl ZoneAttributes {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// The room icon, e.g. `x-rincon-roomicon:living`.
+    pub fn icon(&self) -> &str {
+        &self.icon
+    }
+    pub fn configuration(&self) -> &str {
+        &self.configuration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eq_type_wire_names() {
+        assert_eq!(EqType::NightMode.to_string(), "NightMode");
+        assert_eq!(EqType::DialogLevel.to_string(), "DialogLevel");
+        assert_eq!(EqType::SubGain.to_string(), "SubGain");
+        assert_eq!(EqType::SubEnabled.to_string(), "SubEnable");
+        assert_eq!(EqType::SurroundLevel.to_string(), "SurroundLevel");
+        assert_eq!(EqType::SurroundEnabled.to_string(), "SurroundEnable");
+        assert_eq!(EqType::MusicSurroundLevel.to_string(), "MusicSurroundLevel");
+        assert_eq!(EqType::HeightChannelLevel.to_string(), "HeightChannelLevel");
+        assert_eq!(EqType::SurroundMode.to_string(), "SurroundMode");
+    }
+
+    #[test]
+    fn test_unknown_transport_state() {
+        assert_eq!(TransportState::from("PAUSED_PLAYBACK"), TransportState::PausedPlayback);
+        assert_eq!(
+            TransportState::from("RECORDING"),
+            TransportState::Other("RECORDING".to_string())
+        );
+    }
 }
@@ -0,0 +1,591 @@
+use crate::{
+    manager::escape::{escape_str_attribute, escape_str_pcdata},
+    ContentKind, Result,
+};
+use roxmltree::{Document, Node};
+use std::{fmt, str::FromStr};
+
+const DIDL_NAMESPACE: &str = "urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/";
+/// The namespace of the `desc` elements Sonos uses to tell which music service an item is from.
+pub const RINCON_NAMESPACE: &str = "urn:schemas-rinconnetworks-com:metadata-1-0/";
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+const NAMESPACES: &[(&str, &str)] = &[
+    ("dc", "http://purl.org/dc/elements/1.1/"),
+    ("upnp", "urn:schemas-upnp-org:metadata-1-0/upnp/"),
+    ("r", RINCON_NAMESPACE),
+];
+
+/// A DIDL-Lite document, the XML format UPnP uses to describe media.
+/// It gets parsed with [FromStr](#impl-FromStr) and written with [Display](#impl-Display).
+/// Parsing and writing a document keeps its attributes, namespaces and the order of elements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DidlLite {
+    // (prefix, uri) as declared on the root element, `None` for the default namespace
+    namespaces: Vec<(Option<String>, String)>,
+    objects: Vec<DidlObject>,
+}
+
+impl DidlLite {
+    /// Creates a document containing the given items and containers.
+    pub fn new(objects: Vec<DidlObject>) -> Self {
+        let namespaces = NAMESPACES
+            .iter()
+            .map(|(prefix, uri)| (Some(prefix.to_string()), uri.to_string()))
+            .chain(std::iter::once((None, DIDL_NAMESPACE.to_string())))
+            .collect();
+        Self {
+            namespaces,
+            objects,
+        }
+    }
+
+    pub(crate) fn from_xml(node: Node<'_, '_>) -> Result<Self> {
+        let namespaces = node
+            .namespaces()
+            .iter()
+            .filter(|ns| ns.uri() != XML_NAMESPACE)
+            .map(|ns| {
+                // the well-known namespaces always get their usual prefix, see `qualified_name`
+                let prefix = NAMESPACES
+                    .iter()
+                    .find(|(_, uri)| *uri == ns.uri())
+                    .map(|(prefix, _)| *prefix)
+                    .or_else(|| ns.name());
+                (prefix.map(str::to_string), ns.uri().to_string())
+            })
+            .collect();
+        let objects = node
+            .children()
+            .filter(Node::is_element)
+            .filter(|c| matches!(c.tag_name().name(), "item" | "container"))
+            .map(DidlObject::from_xml)
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            namespaces,
+            objects,
+        })
+    }
+
+    /// The items and containers of the document.
+    pub fn objects(&self) -> &[DidlObject] {
+        &self.objects
+    }
+
+    /// Consumes the document, returning its items and containers.
+    pub fn into_objects(self) -> Vec<DidlObject> {
+        self.objects
+    }
+}
+
+impl Default for DidlLite {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl From<DidlObject> for DidlLite {
+    fn from(object: DidlObject) -> Self {
+        Self::new(vec![object])
+    }
+}
+
+impl FromStr for DidlLite {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let doc = Document::parse(s)?;
+        Self::from_xml(doc.root_element())
+    }
+}
+
+impl fmt::Display for DidlLite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<DIDL-Lite")?;
+        for (prefix, namespace) in &self.namespaces {
+            match prefix {
+                Some(prefix) => write!(
+                    f,
+                    r#" xmlns:{}="{}""#,
+                    prefix,
+                    escape_str_attribute(namespace)
+                )?,
+                None => write!(f, r#" xmlns="{}""#, escape_str_attribute(namespace))?,
+            }
+        }
+        f.write_str(">")?;
+        for object in &self.objects {
+            write!(f, "{}", object)?;
+        }
+        f.write_str("</DIDL-Lite>")
+    }
+}
+
+/// An item or container of a [DidlLite](struct.DidlLite.html) document.
+/// Besides its attributes like `id` it holds a list of properties like `dc:title` or
+/// `upnp:class`, the resources it can be played from and `desc` extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DidlObject {
+    kind: ContentKind,
+    attributes: Vec<(String, String)>,
+    properties: Vec<DidlProperty>,
+    resources: Vec<DidlResource>,
+    descs: Vec<DidlDesc>,
+    // the document order of the child elements
+    order: Vec<Child>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Child {
+    Property(usize),
+    Resource(usize),
+    Desc(usize),
+}
+
+#[allow(missing_docs)]
+impl DidlObject {
+    /// Creates an empty item.
+    pub fn item(id: impl Into<String>, parent_id: impl Into<String>) -> Self {
+        Self::new(ContentKind::Item, id.into(), parent_id.into())
+    }
+
+    /// Creates an empty container.
+    pub fn container(id: impl Into<String>, parent_id: impl Into<String>) -> Self {
+        Self::new(ContentKind::Container, id.into(), parent_id.into())
+    }
+
+    fn new(kind: ContentKind, id: String, parent_id: String) -> Self {
+        Self {
+            kind,
+            attributes: vec![
+                ("id".to_string(), id),
+                ("restricted".to_string(), "true".to_string()),
+                ("parentID".to_string(), parent_id),
+            ],
+            properties: Vec::new(),
+            resources: Vec::new(),
+            descs: Vec::new(),
+            order: Vec::new(),
+        }
+    }
+
+    pub(crate) fn from_xml(node: Node<'_, '_>) -> Result<Self> {
+        let kind = match node.tag_name().name() {
+            "container" => ContentKind::Container,
+            _ => ContentKind::Item,
+        };
+        let mut object = Self {
+            kind,
+            attributes: qualified_element(node).1,
+            properties: Vec::new(),
+            resources: Vec::new(),
+            descs: Vec::new(),
+            order: Vec::new(),
+        };
+
+        for child in node.children().filter(Node::is_element) {
+            let (name, attributes) = qualified_element(child);
+            let value = child.text().unwrap_or_default().to_string();
+            let tag_name = child.tag_name();
+
+            match (tag_name.namespace(), tag_name.name()) {
+                (Some(DIDL_NAMESPACE), "res") | (None, "res") => {
+                    object.add_resource(DidlResource {
+                        uri: value,
+                        attributes,
+                    });
+                }
+                (Some(DIDL_NAMESPACE), "desc") | (None, "desc") => {
+                    object.add_desc(DidlDesc {
+                        id: child.attribute("id").unwrap_or_default().to_string(),
+                        name_space: child.attribute("nameSpace").unwrap_or_default().to_string(),
+                        value,
+                    });
+                }
+                _ => {
+                    object.order.push(Child::Property(object.properties.len()));
+                    object.properties.push(DidlProperty {
+                        name,
+                        attributes,
+                        value,
+                    });
+                }
+            }
+        }
+
+        Ok(object)
+    }
+
+    pub fn kind(&self) -> ContentKind {
+        self.kind
+    }
+    pub fn id(&self) -> &str {
+        self.attribute("id").unwrap_or_default()
+    }
+    pub fn parent_id(&self) -> &str {
+        self.attribute("parentID").unwrap_or_default()
+    }
+    pub fn restricted(&self) -> bool {
+        matches!(self.attribute("restricted"), Some("true") | Some("1"))
+    }
+    pub fn set_restricted(&mut self, restricted: bool) -> &mut Self {
+        self.set_attribute("restricted", restricted.to_string())
+    }
+
+    /// All attributes of the item or container, including `id`, `parentID` and `restricted`,
+    /// e.g. `childCount` or `refID`.
+    pub fn attributes(&self) -> &[(String, String)] {
+        &self.attributes
+    }
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        find_attribute(&self.attributes, name)
+    }
+    pub fn set_attribute(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> &mut Self {
+        set_attribute(&mut self.attributes, name.into(), value.into());
+        self
+    }
+
+    /// All properties in document order.
+    pub fn properties(&self) -> &[DidlProperty] {
+        &self.properties
+    }
+    /// The value of the first property with the given name, e.g. `dc:title` or `r:streamContent`.
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| p.value.as_str())
+    }
+    /// Sets the value of the first property with the given name, or appends it if there is none.
+    pub fn set_property(&mut self, name: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        let name = name.into();
+        let value = value.into();
+        match self.properties.iter_mut().find(|p| p.name == name) {
+            Some(property) => property.value = value,
+            None => {
+                self.order.push(Child::Property(self.properties.len()));
+                self.properties.push(DidlProperty {
+                    name,
+                    attributes: Vec::new(),
+                    value,
+                });
+            }
+        }
+        self
+    }
+
+    /// The `dc:title`.
+    pub fn title(&self) -> Option<&str> {
+        self.property("dc:title")
+    }
+    pub fn set_title(&mut self, title: impl Into<String>) -> &mut Self {
+        self.set_property("dc:title", title)
+    }
+    /// The `upnp:class`, e.g. `object.item.audioItem.musicTrack`.
+    pub fn class(&self) -> Option<&str> {
+        self.property("upnp:class")
+    }
+    pub fn set_class(&mut self, class: impl Into<String>) -> &mut Self {
+        self.set_property("upnp:class", class)
+    }
+    /// The `dc:creator`.
+    pub fn creator(&self) -> Option<&str> {
+        self.property("dc:creator")
+    }
+    /// The `upnp:album`.
+    pub fn album(&self) -> Option<&str> {
+        self.property("upnp:album")
+    }
+    /// The `upnp:albumArtURI`. Sonos reports it relative to the speaker for local files.
+    pub fn album_art_uri(&self) -> Option<&str> {
+        self.property("upnp:albumArtURI")
+    }
+
+    pub fn resources(&self) -> &[DidlResource] {
+        &self.resources
+    }
+    pub fn add_resource(&mut self, resource: DidlResource) -> &mut Self {
+        self.order.push(Child::Resource(self.resources.len()));
+        self.resources.push(resource);
+        self
+    }
+
+    pub fn descs(&self) -> &[DidlDesc] {
+        &self.descs
+    }
+    pub fn add_desc(&mut self, desc: DidlDesc) -> &mut Self {
+        self.order.push(Child::Desc(self.descs.len()));
+        self.descs.push(desc);
+        self
+    }
+}
+
+impl fmt::Display for DidlObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tag = match self.kind {
+            ContentKind::Item => "item",
+            ContentKind::Container => "container",
+        };
+        write!(f, "<{}", tag)?;
+        write_attributes(f, &self.attributes)?;
+        f.write_str(">")?;
+        for child in &self.order {
+            match *child {
+                Child::Resource(i) => {
+                    let resource = &self.resources[i];
+                    write_element(f, "res", &resource.attributes, &resource.uri)?;
+                }
+                Child::Property(i) => {
+                    let property = &self.properties[i];
+                    write_element(f, &property.name, &property.attributes, &property.value)?;
+                }
+                Child::Desc(i) => {
+                    let desc = &self.descs[i];
+                    write!(
+                        f,
+                        r#"<desc id="{}" nameSpace="{}">{}</desc>"#,
+                        escape_str_attribute(&desc.id),
+                        escape_str_attribute(&desc.name_space),
+                        escape_str_pcdata(&desc.value)
+                    )?;
+                }
+            }
+        }
+        write!(f, "</{}>", tag)
+    }
+}
+
+fn write_element(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    attributes: &[(String, String)],
+    value: &str,
+) -> fmt::Result {
+    write!(f, "<{}", name)?;
+    write_attributes(f, attributes)?;
+    write!(f, ">{}</{}>", escape_str_pcdata(value), name)
+}
+
+fn write_attributes(f: &mut fmt::Formatter<'_>, attributes: &[(String, String)]) -> fmt::Result {
+    for (key, value) in attributes {
+        write!(f, r#" {}="{}""#, key, escape_str_attribute(value))?;
+    }
+    Ok(())
+}
+
+fn find_attribute<'a>(attributes: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+fn set_attribute(attributes: &mut Vec<(String, String)>, name: String, value: String) {
+    match attributes.iter_mut().find(|(key, _)| *key == name) {
+        Some(attribute) => attribute.1 = value,
+        None => attributes.push((name, value)),
+    }
+}
+
+/// The name and attributes of an element with their prefixes. Declarations of namespaces the
+/// `DIDL-Lite` root doesn't declare come first in the attributes.
+fn qualified_element(node: Node<'_, '_>) -> (String, Vec<(String, String)>) {
+    let mut attributes = Vec::new();
+    let tag_name = node.tag_name();
+    let name = qualified_name(node, tag_name.namespace(), tag_name.name(), &mut attributes);
+    let mut values = Vec::new();
+    for attribute in node.attributes() {
+        let name = qualified_name(
+            node,
+            attribute.namespace(),
+            attribute.name(),
+            &mut attributes,
+        );
+        values.push((name, attribute.value().to_string()));
+    }
+    attributes.extend(values);
+    (name, attributes)
+}
+
+/// Prefixes the name of an element or attribute with the prefix of its namespace.
+/// The namespaces of `dc`, `upnp` and `r` always get these prefixes so that properties can be
+/// looked up by them, other namespaces keep the prefix of the document. If the `DIDL-Lite` root
+/// doesn't declare such a namespace, its declaration is added to `attributes`.
+fn qualified_name(
+    node: Node<'_, '_>,
+    namespace: Option<&str>,
+    name: &str,
+    attributes: &mut Vec<(String, String)>,
+) -> String {
+    let namespace = match namespace {
+        None | Some(DIDL_NAMESPACE) => return name.to_string(),
+        Some(namespace) => namespace,
+    };
+    if let Some((prefix, _)) = NAMESPACES.iter().find(|(_, uri)| *uri == namespace) {
+        return format!("{}:{}", prefix, name);
+    }
+
+    let prefix = node.lookup_prefix(namespace);
+    let root = node.document().root_element();
+    if root.lookup_namespace_uri(prefix) != Some(namespace) {
+        let declaration = match prefix {
+            Some(prefix) => format!("xmlns:{}", prefix),
+            None => "xmlns".to_string(),
+        };
+        if find_attribute(attributes, &declaration).is_none() {
+            attributes.push((declaration, namespace.to_string()));
+        }
+    }
+    match prefix {
+        Some(prefix) => format!("{}:{}", prefix, name),
+        None => name.to_string(),
+    }
+}
+
+/// A property of a [DidlObject](struct.DidlObject.html), like `<dc:title>Help!</dc:title>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DidlProperty {
+    name: String,
+    attributes: Vec<(String, String)>,
+    value: String,
+}
+
+#[allow(missing_docs)]
+impl DidlProperty {
+    /// The name including the namespace prefix, e.g. `upnp:albumArtURI`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn attributes(&self) -> &[(String, String)] {
+        &self.attributes
+    }
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// A resource an item can be played from, along with attributes like its `protocolInfo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DidlResource {
+    uri: String,
+    attributes: Vec<(String, String)>,
+}
+
+#[allow(missing_docs)]
+impl DidlResource {
+    pub fn new(uri: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            attributes: Vec::new(),
+        }
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        find_attribute(&self.attributes, name)
+    }
+    pub fn set_attribute(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> &mut Self {
+        set_attribute(&mut self.attributes, name.into(), value.into());
+        self
+    }
+    /// E.g. `sonos.com-spotify:*:audio/x-spotify:*`.
+    pub fn protocol_info(&self) -> Option<&str> {
+        self.attribute("protocolInfo")
+    }
+    /// The duration in the `h:mm:ss` format.
+    pub fn duration(&self) -> Option<&str> {
+        self.attribute("duration")
+    }
+}
+
+/// A `desc` extension of an item, which Sonos uses to reference the account of a music service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DidlDesc {
+    id: String,
+    name_space: String,
+    value: String,
+}
+
+#[allow(missing_docs)]
+impl DidlDesc {
+    pub fn new(
+        id: impl Into<String>,
+        name_space: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            name_space: name_space.into(),
+            value: value.into(),
+        }
+    }
+
+    /// E.g. `cdudn`.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+    pub fn name_space(&self) -> &str {
+        &self.name_space
+    }
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUEUE_ITEM: &str = r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns:r="urn:schemas-rinconnetworks-com:metadata-1-0/" xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"><item id="Q:0/1" parentID="Q:0" restricted="true"><res protocolInfo="sonos.com-spotify:*:audio/x-spotify:*" duration="0:03:21">x-sonos-spotify:spotify%3atrack%3a4LI1ykYGFCcXPWkrpcU7hn?sid=12&amp;flags=8224&amp;sn=1</res><upnp:albumArtURI>/getaa?s=1&amp;u=x-sonos-spotify%3aspotify%253atrack%253a4LI1ykYGFCcXPWkrpcU7hn</upnp:albumArtURI><dc:title>Rock &amp; Roll</dc:title><upnp:class>object.item.audioItem.musicTrack</upnp:class><dc:creator>Led Zeppelin</dc:creator><upnp:album>Led Zeppelin IV</upnp:album><r:streamContent></r:streamContent><desc id="cdudn" nameSpace="urn:schemas-rinconnetworks-com:metadata-1-0/">SA_RINCON3079_X_#Svc3079-0-Token</desc></item></DIDL-Lite>"#;
+
+    #[test]
+    fn test_parse_queue_item() -> Result<()> {
+        let didl: DidlLite = QUEUE_ITEM.parse()?;
+        let track = &didl.objects()[0];
+
+        assert_eq!(track.kind(), ContentKind::Item);
+        assert_eq!(track.id(), "Q:0/1");
+        assert_eq!(track.parent_id(), "Q:0");
+        assert_eq!(track.title(), Some("Rock & Roll"));
+        assert_eq!(track.class(), Some("object.item.audioItem.musicTrack"));
+        assert_eq!(track.property("r:streamContent"), Some(""));
+        assert_eq!(track.resources()[0].duration(), Some("0:03:21"));
+        assert_eq!(
+            track.resources()[0].protocol_info(),
+            Some("sonos.com-spotify:*:audio/x-spotify:*")
+        );
+        assert_eq!(track.descs()[0].value(), "SA_RINCON3079_X_#Svc3079-0-Token");
+        Ok(())
+    }
+
+    const ALBUM: &str = r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns:dlna="urn:schemas-dlna-org:metadata-1-0/"><container id="A:ALBUM/Abbey%20Road" parentID="A:ALBUM" restricted="true" childCount="17" searchable="1"><dc:title>Abbey Road</dc:title><upnp:class>object.container.album.musicAlbum</upnp:class><res protocolInfo="x-rincon-playlist:*:*:*" dlna:profileID="MP3">x-rincon-playlist:RINCON_000E58A0B1C201400#A:ALBUM/Abbey%20Road</res><dlna:containerType>Tuner_1_0</dlna:containerType><ms:rating xmlns:ms="urn:example:ratings" stars="5"></ms:rating><upnp:albumArtURI>/getaa?u=x-file-cifs%3a%2f%2fnas%2fabbey.mp3</upnp:albumArtURI></container></DIDL-Lite>"#;
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        for input in &[QUEUE_ITEM, ALBUM] {
+            let didl: DidlLite = input.parse()?;
+            assert_eq!(&didl.to_string(), input);
+            assert_eq!(didl.to_string().parse::<DidlLite>()?, didl);
+        }
+
+        let album: DidlLite = ALBUM.parse()?;
+        let album = &album.objects()[0];
+        assert_eq!(album.attribute("childCount"), Some("17"));
+        assert_eq!(album.property("dlna:containerType"), Some("Tuner_1_0"));
+        assert_eq!(
+            album.resources()[0].attribute("dlna:profileID"),
+            Some("MP3")
+        );
+        Ok(())
+    }
+}
@@ -43,32 +43,45 @@
 //! # async fn f() -> Result<(), sonor::Error> {
 //! # let speaker = sonor::find("your room name", Duration::from_secs(2)).await?.expect("room exists");
 //!
-//! let service = URN::service("schemas-upnp-org", "GroupRenderingControl", 1);
-//! let args = "<InstanceID>0</InstanceID>";
-//! let response = speaker.action(&service, "GetGroupMute", args).await?;
+//! let service = URN::service("schemas-upnp-org", "AlarmClock", 1);
+//! let response = speaker.action(&service, "GetTimeNow", "").await?;
 //!
-//! println!("{}", response["CurrentMute"]);
+//! println!("{}", response["CurrentLocalTime"]);
 //!
 //! # Ok(())
 //! # };
 //! ```
 
 pub mod manager;
+mod album_art;
+mod alarm;
 mod datatypes;
+mod didl;
 mod discovery;
+mod music_service;
 mod snapshot;
 mod speaker;
+mod topology;
 mod track;
 mod content;
 mod utils;
 
-pub use datatypes::{RepeatMode, SpeakerInfo};
+pub use album_art::{AlbumArt, AlbumArtCache};
+pub use alarm::{Alarm, Recurrence};
+pub use content::{BrowseResult, Content, ContentKind, LibraryCategory};
+pub use datatypes::{
+    EnqueuedTracks, EqType, MediaInfo, RepeatMode, SpeakerInfo, SurroundMode, TransportInfo, TransportState,
+    ZoneAttributes, ZoneInfo,
+};
+pub use didl::{DidlDesc, DidlLite, DidlObject, DidlProperty, DidlResource, RINCON_NAMESPACE};
 pub use discovery::{discover, discover_one, find};
+pub use music_service::{AuthPolicy, MusicService};
 pub use rupnp::{self, http::Uri, ssdp::URN, Service};
 pub use snapshot::Snapshot;
 pub use speaker::Speaker;
+pub use topology::{ChannelMap, VanishedDevice, ZoneGroup, ZoneGroupMember, ZoneGroupState};
 use thiserror::*;
-pub use track::{Track, TrackInfo};
+pub use track::{PositionInfo, Track, TrackHints, TrackInfo};
 
 /// Represents an error encountered by Sonor
 #[derive(Error, Debug)]
@@ -82,6 +95,12 @@ pub enum Error {
     /// Errors source from URI manipulation
     #[error(transparent)]
     InvalidUri(#[from] http::uri::InvalidUri),
+    /// Errors sourced from plain HTTP requests, e.g. when fetching album art
+    #[error(transparent)]
+    Http(#[from] HttpError),
+    /// Fetching an `https` URI needs the `https` feature of this crate
+    #[error("{0} can only be fetched with the `https` feature enabled")]
+    HttpsNotEnabled(String),
     /// This error is produced when attempting to perform an action and
     /// the specified service is not present.
     #[error("Service {service} was not found when performing {action} with {payload}")]
@@ -96,6 +115,16 @@ pub enum Error {
     /// The discovery can return an empty stream of speakers.
     #[error("No speakers detected")]
     NoSpeakersDetected,
+    /// The speaker doesn't support the requested setting, e.g. a night mode on a
+    /// speaker without TV input.
+    #[error("{0} is not supported by this speaker")]
+    Unsupported(String),
+    /// Alarms have to be created before they can be updated.
+    #[error("The alarm has no ID, it has to be created first")]
+    AlarmWithoutId,
+    /// The speaker is expected to be the visible speaker of a stereo pair, but isn't.
+    #[error("The speaker is not the left speaker of a stereo pair")]
+    NotAStereoPair,
     /// An impossible? situation where a speaker isn't included
     /// in its own zone group state
     #[error("asked for zone group state but the speaker doesn't seem to be included there")]
@@ -109,4 +138,9 @@ pub enum Error {
     NonSonosDevicesInSonosUPnPDiscovery,
 }
 
+/// An error of an HTTP request that doesn't go through UPnP, e.g. when fetching album art.
+#[derive(Error, Debug)]
+#[error(transparent)]
+pub struct HttpError(hyper::Error);
+
 type Result<T, E = Error> = std::result::Result<T, E>;
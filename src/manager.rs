@@ -5,7 +5,7 @@
 
 mod controller;
 mod mediasource;
-mod metadata;
+pub(crate) mod metadata;
 mod error;
 pub mod escape;
 mod subscriber;
@@ -23,6 +23,7 @@ pub use error::Error;
 pub use mediasource::MediaSource;
 pub(self) use controller::ZoneAction;
 
+use std::time::Duration;
 use tokio::{sync::oneshot, task::JoinHandle};
 
 #[derive(Default, Debug)]
@@ -67,6 +68,20 @@ impl<'a> Zone<'a> {
         Ok(())
     }
 
+    /// Rename the room of this zone and optionally change its icon. This zone
+    /// keeps pointing at the room under its new name, but zones are looked up
+    /// by name, so any other `Zone` for the same room stops working until it
+    /// calls [update_room](#method.update_room) with the new name.
+    pub async fn rename(&mut self, new_name: String, icon: Option<String>) -> Result<()> {
+        match self.action(ZoneAction::Rename(new_name.clone(), icon)).await? {
+            Response::Ok(_) => {
+                self.name = new_name;
+                Ok(())
+            }
+            _ => Err(Error::ZoneActionError),
+        }
+    }
+
     action!(play_now: PlayNow(media: MediaSource) => Ok(__: ()));
     action!(queue_as_next: QueueAsNext(media: MediaSource) => Ok(__: ()));
     action!(play: Play => Ok(__: ()));
@@ -83,9 +98,23 @@ impl<'a> Zone<'a> {
     action!(set_play_mode: SetPlayMode(mode: crate::RepeatMode, state: bool) => Ok(__: ()));
     action!(clear_queue: ClearQueue => Ok(__: ()));
     action!(get_queue: GetQueue => Queue(queue: Vec<Track>));
-    action!(take_snapshot: TakeSnapshot => Snapshot(snap: Snapshot));
+    action!(move_tracks: MoveTracks(start: u32, count: u32, insert_before: u32) => Ok(__: ()));
+    action!(remove_track_range: RemoveTrackRange(start: u32, count: u32) => Ok(__: ()));
+    pub async fn take_snapshot(&self) -> Result<Snapshot> {
+        match self.action(ZoneAction::TakeSnapshot).await? {
+            Response::Snapshot(snap) => Ok(*snap),
+            _ => Err(Error::ZoneActionError),
+        }
+    }
     action!(apply_snapshot: ApplySnapshot(snap: Snapshot) => Ok(__: ()));
     action!(set_rel_volume: SetRelVolume(number: i32) => Ok(__: ()));
+    action!(group_volume: GetGroupVolume => Volume(volume: u32));
+    action!(set_group_volume: SetGroupVolume(volume: u32) => Ok(__: ()));
+    action!(set_rel_group_volume: SetRelGroupVolume(adjustment: i32) => Ok(__: ()));
+    action!(group_mute: GetGroupMute => Mute(mute: bool));
+    action!(set_group_mute: SetGroupMute(mute: bool) => Ok(__: ()));
+    action!(set_sleep_timer: SetSleepTimer(duration: Option<Duration>) => Ok(__: ()));
+    action!(remaining_sleep_timer: GetSleepTimer => SleepTimer(remaining: Option<Duration>));
 
 }
 
@@ -122,6 +151,22 @@ impl Manager {
         })
     }
 
+    /// The full topology of the system as last reported by the speakers, including
+    /// bonded speakers and vanished devices.
+    pub async fn topology(&self) -> Result<crate::ZoneGroupState> {
+        let (tx, rx) = oneshot::channel();
+        self.tx
+            .as_ref()
+            .ok_or(Error::ControllerNotInitialized)?
+            .send(Command::GetTopology(tx))
+            .await
+            .map_err(|_| Error::ControllerOffline)?;
+        match rx.await.map_err(|_| Error::MessageRecvError)? {
+            Response::Topology(topology) => Ok(topology),
+            _ => Err(Error::ZoneActionError),
+        }
+    }
+
     pub async fn get_zone(&self, room_name: String) -> Result<Zone<'_>> {
         let zone = Zone {
             manager: self,
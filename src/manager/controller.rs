@@ -7,13 +7,14 @@ mod zoneaction;
 use super::{
     subscriber::Subscriber,
     types::{
-        AVStatus, CmdSender, Event, EventReceiver, ReducedTopology, Responder, Topology, Uuid,
+        AVStatus, CmdSender, Event, EventReceiver, ReducedTopology, Responder, Response, Topology,
+        Uuid,
     },
     Command, Error, Result,
 };
 use crate::{
     discover_one, find, speaker::AV_TRANSPORT, speaker::ZONE_GROUP_TOPOLOGY, Service, Speaker,
-    SpeakerInfo, Uri, URN,
+    SpeakerInfo, TransportState, Uri, URN,
 };
 use futures_util::stream::{SelectAll, StreamExt};
 use log::{debug, warn};
@@ -46,6 +47,7 @@ impl SpeakerData {
     pub(crate) async fn get_current_track_no(&self) -> Result<u32> {
         match self
             .transport_data
+            .vars
             .iter()
             .find(|(k, _)| k.eq_ignore_ascii_case("CurrentTrack"))
         {
@@ -61,6 +63,19 @@ impl SpeakerData {
                 .map_err(Error::from),
         }
     }
+
+    /// Toggle playback on this speaker. Take the transport state from cache if
+    /// available, otherwise ask for it.
+    pub(crate) async fn play_or_pause(&self) -> Result<()> {
+        match self.transport_data.transport_state {
+            Some(TransportState::Playing) | Some(TransportState::Transitioning) => {
+                self.speaker.pause().await
+            }
+            Some(_) => self.speaker.play().await,
+            None => self.speaker.play_or_pause().await,
+        }
+        .map_err(Error::from)
+    }
 }
 
 #[derive(Debug, Default)]
@@ -69,6 +84,7 @@ impl SpeakerData {
 pub(super) struct Controller {
     speakerdata: Vec<SpeakerData>,
     topology: ReducedTopology,
+    system_topology: Topology,
     topology_subscription: Subscriber,
     queued_event_handles: Vec<EventReceiver>,
     rx: Option<CmdReceiver>,
@@ -106,13 +122,11 @@ impl Controller {
 
     async fn discover_system(&mut self) -> Result<()> {
         let topology = match self.seed.as_ref() {
-            Some(speaker) => speaker._zone_group_state().await?.into_iter().collect(),
+            Some(speaker) => speaker.zone_group_state().await?,
             None => discover_one(Duration::from_secs(5))
                 .await?
-                ._zone_group_state()
-                .await?
-                .into_iter()
-                .collect(),
+                .zone_group_state()
+                .await?,
         };
         self.update_from_topology(topology)
             .await
@@ -141,7 +155,8 @@ impl Controller {
 
     /// Update speakers and topology
     async fn update_from_topology(&mut self, system_topology: Topology) -> Result<()> {
-        let topology: ReducedTopology = system_topology
+        let rooms = system_topology.rooms();
+        let topology: ReducedTopology = rooms
             .iter()
             .map(|(uuid, infos)| {
                 (
@@ -150,7 +165,7 @@ impl Controller {
                 )
             })
             .collect();
-        let infos: Vec<SpeakerInfo> = system_topology
+        let infos: Vec<SpeakerInfo> = rooms
             .into_iter()
             .flat_map(|(_, infos)| infos)
             .collect();
@@ -192,6 +207,7 @@ impl Controller {
         }
 
         self.topology = topology;
+        self.system_topology = system_topology;
         Ok(())
     }
 
@@ -246,13 +262,17 @@ impl Controller {
                 debug!(
                     "Got topology update: {}",
                     topology
+                        .groups()
                         .iter()
-                        .map(|(u, s)| format!(
+                        .map(|g| format!(
                             "{} => {:?}, ",
-                            self.get_speaker_by_uuid(u)
+                            self.get_speaker_by_uuid(g.coordinator())
                                 .map(|s| s.name())
                                 .unwrap_or_default(),
-                            s.iter().map(|i| i.name()).collect::<Vec<&str>>()
+                            g.members()
+                                .iter()
+                                .map(|m| m.info().name())
+                                .collect::<Vec<&str>>()
                         ))
                         .collect::<String>()
                 );
@@ -278,7 +298,8 @@ impl Controller {
                 );
                 debug!(
                     "... {:?}",
-                    data.iter()
+                    data.vars
+                        .iter()
                         .filter(|(s, _)| keys.contains(&s.as_str()))
                         .collect::<Vec<&(String, String)>>()
                 );
@@ -354,13 +375,13 @@ impl Controller {
     /// Handle zone actions. Deal with errors here. Only return an error if it
     /// is unrecoverable and should break the non-event loop.
     async fn handle_zone_action(
-        &self,
+        &mut self,
         tx: Responder,
         name: String,
         action: ZoneAction,
     ) -> Result<()> {
         debug!("Got {:?}", action);
-        action.handle_action(&self, tx, name).await
+        action.handle_action(self, tx, name).await
     }
 
     /// Run the event loop.
@@ -392,6 +413,7 @@ impl Controller {
                 maybe_command = rx.recv() => match maybe_command {
                     Some(cmd) => match cmd {
                         DoZoneAction(tx, name, action) => self.handle_zone_action(tx, name, action).await?,
+                        GetTopology(tx) => tx.send(Response::Topology(self.system_topology.clone())).unwrap_or(()),
                     },
                     None => break
                 },
@@ -417,6 +439,13 @@ impl Controller {
         })
     }
 
+    fn get_speaker_with_name_mut(&mut self, name: &str) -> Option<&mut Speaker> {
+        self.speakerdata
+            .iter_mut()
+            .find(|s| s.speaker.info.name().eq_ignore_ascii_case(name))
+            .map(|s| &mut s.speaker)
+    }
+
     fn get_speaker_by_uuid(&self, uuid: &str) -> Option<&Speaker> {
         self.speakerdata.iter().find_map(|s| {
             match s.speaker.info.uuid().eq_ignore_ascii_case(uuid) {
@@ -469,7 +498,7 @@ impl Controller {
         self.get_speakerdata_by_uuid(coordinator_uuid)
     }
 
-    fn update_avtransport_data(&mut self, uuid: Uuid, data: Vec<(String, String)>) {
+    fn update_avtransport_data(&mut self, uuid: Uuid, data: AVStatus) {
         match self
             .speakerdata
             .iter_mut()
@@ -1,4 +1,4 @@
-use std::convert::TryInto;
+use std::{convert::TryInto, time::Duration};
 
 use async_trait::async_trait;
 
@@ -30,16 +30,26 @@ pub enum ZoneAction {
     SetPlayMode(RepeatMode, bool),
     ClearQueue,
     GetQueue,
+    MoveTracks(u32, u32, u32),
+    RemoveTrackRange(u32, u32),
     TakeSnapshot,
     ApplySnapshot(Snapshot),
-    SetRelVolume(i32)
+    SetRelVolume(i32),
+    GetGroupVolume,
+    SetGroupVolume(u32),
+    SetRelGroupVolume(i32),
+    GetGroupMute,
+    SetGroupMute(bool),
+    SetSleepTimer(Option<Duration>),
+    GetSleepTimer,
+    Rename(String, Option<String>),
 }
 use ZoneAction::*;
 
 impl ZoneAction {
     pub(super) async fn handle_action(
         self,
-        controller: &Controller,
+        controller: &mut Controller,
         tx: Responder,
         name: String,
     ) -> Result<()> {
@@ -84,7 +94,9 @@ impl ZoneAction {
             }
             Play => action!( coordinator.play: get_coordinator_for_name -> Ok(__) ),
             Pause => action!( coordinator.pause: get_coordinator_for_name -> Ok(__) ),
-            PlayPause => action!( coordinator.play_or_pause: get_coordinator_for_name -> Ok(__) ),
+            PlayPause => {
+                action!( coordinatordata.play_or_pause: get_coordinatordata_for_name -> Ok(__) )
+            }
             NextTrack => action!( coordinator.next: get_coordinator_for_name -> Ok(__) ),
             PreviousTrack => action!( coordinator.previous: get_coordinator_for_name -> Ok(__) ),
             SeekTime(seconds) => {
@@ -116,11 +128,52 @@ impl ZoneAction {
             }
             ClearQueue => action!( coordinator.clear_queue: get_coordinator_for_name -> Ok(__) ),
             GetQueue => action!( coordinator.queue: get_coordinator_for_name -> Queue(queue) ),
+            MoveTracks(start, count, insert_before) => {
+                if let Some(coordinator) = controller.get_coordinator_for_name(&name) {
+                    log::debug!("Attempting to move tracks in {}", name);
+                    let result = async {
+                        let update_id = coordinator.queue_update_id().await?;
+                        coordinator
+                            .move_tracks(start, count, insert_before, update_id)
+                            .await
+                    };
+                    match result.await {
+                        Ok(()) => return tx.send(Response::Ok(())).or_else(|_| Ok(())),
+                        Err(e) => log::warn!("Error: {}", e),
+                    }
+                }
+                tx.send(Response::NotOk).ok();
+            }
+            RemoveTrackRange(start, count) => {
+                if let Some(coordinator) = controller.get_coordinator_for_name(&name) {
+                    log::debug!("Attempting to remove tracks in {}", name);
+                    let result = async {
+                        let update_id = coordinator.queue_update_id().await?;
+                        coordinator.remove_track_range(start, count, update_id).await
+                    };
+                    match result.await {
+                        Ok(_) => return tx.send(Response::Ok(())).or_else(|_| Ok(())),
+                        Err(e) => log::warn!("Error: {}", e),
+                    }
+                }
+                tx.send(Response::NotOk).ok();
+            }
             ApplySnapshot(snapshot) => {
                 action!( snapshot.apply(coordinator: get_coordinator_for_name) -> Ok(__) )
             }
             TakeSnapshot => {
-                action!( coordinator.snapshot: get_coordinator_for_name -> Snapshot(snapshot) )
+                if let Some(coordinator) = controller.get_coordinator_for_name(&name) {
+                    log::debug!("Attempting to take a snapshot in {}", name);
+                    match coordinator.snapshot().await {
+                        Ok(snapshot) => {
+                            return tx
+                                .send(Response::Snapshot(Box::new(snapshot)))
+                                .or_else(|_| Ok(()))
+                        }
+                        Err(e) => log::warn!("Error: {}", e),
+                    }
+                }
+                tx.send(Response::NotOk).ok();
             }
             Exists => {
                 if controller
@@ -134,6 +187,37 @@ impl ZoneAction {
                 }
             }
             SetRelVolume(number) => action!( number.set_rel_volume(coordinator: get_coordinator_for_name) -> Ok(__) ),
+            GetGroupVolume => {
+                action!( coordinator.group_volume: get_coordinator_for_name -> Volume(volume) )
+            }
+            SetGroupVolume(volume) => {
+                action!( volume.set_group_volume(coordinator: get_coordinator_for_name) -> Ok(__) )
+            }
+            SetRelGroupVolume(adjustment) => {
+                action!( adjustment.set_rel_group_volume(coordinator: get_coordinator_for_name) -> Ok(__) )
+            }
+            GetGroupMute => action!( coordinator.group_mute: get_coordinator_for_name -> Mute(mute) ),
+            SetGroupMute(state) => {
+                action!( state.set_group_mute(coordinator: get_coordinator_for_name) -> Ok(__) )
+            }
+            SetSleepTimer(duration) => {
+                action!( duration.set_sleep_timer(coordinator: get_coordinator_for_name) -> Ok(__) )
+            }
+            GetSleepTimer => {
+                action!( coordinator.remaining_sleep_timer: get_coordinator_for_name -> SleepTimer(remaining) )
+            }
+            Rename(new_name, icon) => {
+                // Renames the room itself rather than its coordinator. The speaker updates its
+                // cached info, so the zone resolves by the new name before the topology event.
+                if let Some(speaker) = controller.get_speaker_with_name_mut(&name) {
+                    log::debug!("Attempting to rename {} to {}", name, new_name);
+                    match speaker.rename(&new_name, icon.as_deref()).await {
+                        Ok(()) => return tx.send(Response::Ok(())).or_else(|_| Ok(())),
+                        Err(e) => log::warn!("Error: {}", e),
+                    }
+                }
+                tx.send(Response::NotOk).ok();
+            }
         }
 
         Ok(())
@@ -144,6 +228,7 @@ impl ZoneAction {
 trait ZoneActionBoolExt {
     async fn set_shuffle(self, speaker: &crate::Speaker) -> Result<()>;
     async fn set_crossfade(self, speaker: &crate::Speaker) -> Result<()>;
+    async fn set_group_mute(self, speaker: &crate::Speaker) -> Result<()>;
 }
 
 #[async_trait]
@@ -154,12 +239,16 @@ impl ZoneActionBoolExt for bool {
     async fn set_crossfade(self, speaker: &crate::Speaker) -> Result<()> {
         speaker.set_crossfade(self).await.map_err(Error::from)
     }
+    async fn set_group_mute(self, speaker: &crate::Speaker) -> Result<()> {
+        speaker.set_group_mute(self).await.map_err(Error::from)
+    }
 }
 
 #[async_trait]
 trait ZoneActionUnsignedNExt {
     async fn skip_to(self, speaker: &crate::Speaker) -> Result<()>;
     async fn seek_track(self, speaker: &crate::Speaker) -> Result<()>;
+    async fn set_group_volume(self, speaker: &crate::Speaker) -> Result<()>;
 }
 
 #[async_trait]
@@ -170,12 +259,18 @@ impl ZoneActionUnsignedNExt for u32 {
     async fn seek_track(self, speaker: &crate::Speaker) -> Result<()> {
         speaker.seek_track(self).await.map_err(Error::from)
     }
+    async fn set_group_volume(self, speaker: &crate::Speaker) -> Result<()> {
+        // Like the Sonos app, keep the volume ratios the group has right now
+        speaker.snapshot_group_volume().await?;
+        speaker.set_group_volume(self).await.map_err(Error::from)
+    }
 }
 
 #[async_trait]
 trait ZoneActionSignedNExt {
     async fn seek_rel_track(self, speaker: &super::SpeakerData) -> Result<()>;
     async fn set_rel_volume(self, speaker: &super::Speaker) -> Result<()>;
+    async fn set_rel_group_volume(self, speaker: &super::Speaker) -> Result<()>;
 
 }
 
@@ -203,4 +298,25 @@ impl ZoneActionSignedNExt for i32 {
     async fn set_rel_volume(self, speaker: &super::Speaker) -> Result<()> {
         speaker.set_volume_relative(self).await.map(|_| ()).map_err(Error::from)
     }
+
+    async fn set_rel_group_volume(self, speaker: &super::Speaker) -> Result<()> {
+        speaker.snapshot_group_volume().await?;
+        speaker
+            .set_relative_group_volume(self)
+            .await
+            .map(|_| ())
+            .map_err(Error::from)
+    }
+}
+
+#[async_trait]
+trait ZoneActionDurationExt {
+    async fn set_sleep_timer(self, speaker: &crate::Speaker) -> Result<()>;
+}
+
+#[async_trait]
+impl ZoneActionDurationExt for Option<Duration> {
+    async fn set_sleep_timer(self, speaker: &crate::Speaker) -> Result<()> {
+        speaker.set_sleep_timer(self).await.map_err(Error::from)
+    }
 }
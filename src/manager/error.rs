@@ -27,4 +27,7 @@ pub enum Error {
     /// Could not parse content
     #[error("Could not find the requested content")]
     ContentNotFound,
+    /// Media that is streamed directly, e.g. a line-in or a radio station, can't be added to the queue
+    #[error("The requested content can not be queued")]
+    NotQueueable,
 }
\ No newline at end of file
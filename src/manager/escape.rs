@@ -9,6 +9,19 @@ enum Value {
 }
 
 impl Value {
+    fn dispatch_for_attribute(c: char) -> Value {
+        match c {
+            '<'  => Value::Str("&lt;"),
+            '>'  => Value::Str("&gt;"),
+            '"'  => Value::Str("&quot;"),
+            '\'' => Value::Str("&apos;"),
+            '&'  => Value::Str("&amp;"),
+            '\n' => Value::Str("&#xA;"),
+            '\r' => Value::Str("&#xD;"),
+            _    => Value::Char(c)
+        }
+    }
+
     fn dispatch_for_pcdata(c: char) -> Value {
         match c {
             '<'  => Value::Str("&lt;"),
@@ -64,6 +77,25 @@ fn escape_str(s: &str, dispatch: fn(char) -> Value) -> Cow<'_, str> {
     p.into_result()
 }
 
+/// Performs escaping of common XML characters inside attribute values.
+///
+/// This function replaces several important markup characters with their
+/// entity equivalents:
+///
+/// * `<` → `&lt;`
+/// * `>` → `&gt;`
+/// * `"` → `&quot;`
+/// * `'` → `&apos;`
+/// * `&` → `&amp;`
+///
+/// The resulting string is safe to use inside XML attribute values or in PCDATA sections.
+///
+/// Does not perform allocations if the given string does not contain escapable characters.
+#[inline]
+pub fn escape_str_attribute(s: &str) -> Cow<'_, str> {
+    escape_str(s, Value::dispatch_for_attribute)
+}
+
 /// Performs escaping of common XML characters inside PCDATA.
 ///
 /// This function replaces several important markup characters with their
@@ -82,12 +114,13 @@ pub fn escape_str_pcdata(s: &str) -> Cow<'_, str> {
 
 #[cfg(test)]
 mod tests {
-    use super::escape_str_pcdata;
+    use super::{escape_str_attribute, escape_str_pcdata};
 
     // TODO: add more tests
 
     #[test]
     fn test_escape_multibyte_code_points() {
         assert_eq!(escape_str_pcdata("☃<"), "☃&lt;");
+        assert_eq!(escape_str_attribute("☃<\""), "☃&lt;&quot;");
     }
 }
@@ -1,6 +1,5 @@
-use super::{Error, Result, SpeakerData, metadata::{apple_uri_and_metadata, spotify_uri_and_metadata}};
-use crate::Speaker;
-use super::escape::escape_str_pcdata;
+use super::{Error, Result, SpeakerData, metadata::{apple_uri_and_metadata, escape_uri_and_metadata, is_radio_uri, library_uri_and_metadata, radio_favorite_uri_and_metadata, radio_stream_uri_and_metadata, spotify_uri_and_metadata, url_uri_and_metadata}};
+use crate::{utils, Speaker, TrackHints};
 
 #[derive(Debug)]
 /// Definitions for media that can be played and queued.
@@ -9,6 +8,20 @@ pub enum MediaSource {
     Spotify(String),
     SonosPlaylist(String),
     SonosFavorite(String),
+    /// An internet radio stream by its URL, e.g. `http://example.com/stream.mp3`.
+    RadioStream(String),
+    /// A station saved in the radio favorites (`R:0/0`), by its title.
+    RadioFavorite(String),
+    /// An HTTP URL with hints for its metadata. Streams are played as radio, files get queued.
+    Url(String, TrackHints),
+    /// The line-in of the room with this name.
+    LineIn(String),
+    /// The TV input of the soundbar coordinating the zone.
+    TvInput,
+    /// An entry of the content directory, e.g. from a library search.
+    Content(crate::Content),
+    /// Several media that are queued in one go. Streams and nested lists can't be part of it.
+    List(Vec<MediaSource>),
 }
 
 use MediaSource::*;
@@ -36,11 +49,82 @@ impl MediaSource {
                     favorite.metadata()?.into(),
                 ))
             }
+            RadioStream(url) => radio_stream_uri_and_metadata(url),
+            Url(url, hints) => url_uri_and_metadata(url, hints),
+            RadioFavorite(item) => {
+                let stations = speaker.browse("R:0/0", 0, 0).await.ok()?;
+                let station = stations
+                    .iter()
+                    .find(|&s| s.title().eq_ignore_ascii_case(item))?;
+                log::debug!("Found radio station {:?}", station);
+                radio_favorite_uri_and_metadata(station)
+            }
+            LineIn(room) => {
+                let topology = speaker.zone_group_state().await.ok()?;
+                let source = topology
+                    .groups()
+                    .iter()
+                    .flat_map(|group| group.members())
+                    .filter(|member| !member.is_invisible())
+                    .map(|member| member.info())
+                    .find(|info| info.name().eq_ignore_ascii_case(room))?;
+                log::debug!("Found line-in of {}", source.name());
+                Some((utils::line_in_uri(source.uuid()), "".into()))
+            }
+            TvInput => Some((utils::tv_uri(speaker.uuid()), "".into())),
+            Content(content) => match content.metadata() {
+                Some(metadata) => Some((content.uri()?.into(), metadata.into())),
+                None => library_uri_and_metadata(content),
+            },
+            List(_) => None,
         }
     }
 
+    /// Resolve the media into escaped uris and metadata, ready to be added to the queue.
+    async fn get_queue_items(&self, speaker: &Speaker) -> Result<Vec<(String, String)>> {
+        let sources = match self {
+            List(sources) => sources.iter().collect(),
+            source => vec![source],
+        };
+        if sources.is_empty() {
+            return Err(Error::ContentNotFound);
+        }
+        let mut items = Vec::with_capacity(sources.len());
+        for source in sources {
+            if source.is_stream() || matches!(source, List(_)) {
+                return Err(Error::NotQueueable);
+            }
+            let (uri, metadata) = source
+                .get_uri_and_metadata(speaker)
+                .await
+                .ok_or(Error::ContentNotFound)?;
+            if is_radio_uri(&uri) {
+                return Err(Error::NotQueueable);
+            }
+            items.push(escape_uri_and_metadata(&uri, &metadata));
+        }
+        Ok(items)
+    }
+
+    /// Add the items to the queue at track number `position`, in one request if there are several.
+    async fn enqueue(speaker: &Speaker, items: &[(String, String)], position: u32) -> Result<()> {
+        match items {
+            [(uri, metadata)] => speaker.queue_next(uri, metadata, Some(position)).await?,
+            items => drop(speaker.queue_many(items, Some(position)).await?),
+        }
+        Ok(())
+    }
+
+    /// Whether the media is played directly by the transport instead of through the queue.
+    fn is_stream(&self) -> bool {
+        matches!(self, LineIn(_) | TvInput | RadioStream(_) | RadioFavorite(_))
+    }
+
     /// Add the media to the end of the queue.
     pub(crate) async fn queue_as_next(&self, coordinator_data: &SpeakerData) -> Result<()> {
+        if self.is_stream() {
+            return Err(Error::NotQueueable);
+        }
         let speaker = &coordinator_data.speaker;
         let cur_track_no = coordinator_data
             .get_current_track_no()
@@ -50,24 +134,31 @@ impl MediaSource {
                 err
             })
             .unwrap_or(0);
-        let (uri, metadata) = self
-            .get_uri_and_metadata(speaker)
-            .await
-            .ok_or(Error::ContentNotFound)?;
-        speaker
-            .queue_next(&uri, &escape_str_pcdata(&metadata), Some(cur_track_no + 1))
-            .await?;
-        Ok(())
+        let items = self.get_queue_items(speaker).await?;
+        Self::enqueue(speaker, &items, cur_track_no + 1).await
     }
     /// Replace what is playing with this
     pub(crate) async fn play_now(&self, coordinator_data: &SpeakerData) -> Result<()> {
         let coordinator = &coordinator_data.speaker;
-        let (uri, metadata) = self
-            .get_uri_and_metadata(coordinator)
-            .await
-            .ok_or(Error::ContentNotFound)?;
+        let items = match self {
+            List(_) => self.get_queue_items(coordinator).await?,
+            source => {
+                let (uri, metadata) = source
+                    .get_uri_and_metadata(coordinator)
+                    .await
+                    .ok_or(Error::ContentNotFound)?;
+                // Streams and radio, also when saved as a Sonos favorite, bypass the queue
+                let is_stream = source.is_stream() || is_radio_uri(&uri);
+                let (uri, metadata) = escape_uri_and_metadata(&uri, &metadata);
+                if is_stream {
+                    coordinator.set_transport_uri(&uri, &metadata).await?;
+                    return coordinator.play().await.map_err(Error::from);
+                }
+                vec![(uri, metadata)]
+            }
+        };
         coordinator.clear_queue().await?;
-        coordinator.queue_next(&uri, &escape_str_pcdata(&metadata), Some(1)).await?;
+        Self::enqueue(coordinator, &items, 1).await?;
         // Turn on queue mode
         let queue_uri = format!("x-rincon-queue:{}#0", coordinator.uuid());
         coordinator.set_transport_uri(&queue_uri, "").await?;
@@ -1,16 +1,19 @@
 //! Guess metadata and uri from strings
 use urlencoding::encode;
+use super::escape::escape_str_pcdata;
+use crate::{Content, TrackHints, didl::{DidlDesc, DidlLite, DidlObject, DidlResource, RINCON_NAMESPACE}, music_service::{account_cdudn, anonymous_cdudn, service_type}};
+
+// The `sid` of the music services the uris are guessed for, see `MusicService`
+const SPOTIFY_SID: u32 = 12;
+const APPLE_MUSIC_SID: u32 = 204;
+const TUNEIN_SID: u32 = 254;
 
 
 fn get_metadata(id: &str, parent_id: &str, upnp_class: &str, cdudn: &str) -> String {
-    format!(concat!(
-        r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns:r="urn:schemas-rinconnetworks-com:metadata-1-0/" xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/">"#,
-            r#"<item id="{id}" restricted="true" parentID="{parent_id}">"#,
-                r#"<upnp:class>{upnp_class}</upnp:class>"#,
-                r#"<desc id="cdudn" nameSpace="urn:schemas-rinconnetworks-com:metadata-1-0/">{cdudn}</desc>"#,
-            r#"</item>"#,
-        r#"</DIDL-Lite>"#), 
-        id=id, parent_id=parent_id, upnp_class=upnp_class, cdudn=cdudn).to_string()
+    let mut item = DidlObject::item(id, parent_id);
+    item.set_class(upnp_class)
+        .add_desc(DidlDesc::new("cdudn", RINCON_NAMESPACE, cdudn));
+    DidlLite::from(item).to_string()
   }
 
   pub(crate) fn spotify_uri_and_metadata(item: &str) -> Option<(String, String)> {
@@ -18,10 +21,10 @@ fn get_metadata(id: &str, parent_id: &str, upnp_class: &str, cdudn: &str) -> Str
     log::debug!("Got Spotify {}: {}",  kind, id);
     let item = format!("spotify:{}", item);
     let item = encode(&item);
-    let cdudn = format!(r"SA_RINCON{region}_X_#Svc{region}-0-Token", region="3079");
+    let cdudn = account_cdudn(service_type(SPOTIFY_SID));
     match kind {
         "album" => Some((
-            format!(r"x-rincon-cpcontainer:0006206c{}?sid=12", item), 
+            format!(r"x-rincon-cpcontainer:0006206c{}?sid={}", item, SPOTIFY_SID), 
             get_metadata(
                 &format!(r"0004206c{}", item),
                 r"", 
@@ -30,7 +33,7 @@ fn get_metadata(id: &str, parent_id: &str, upnp_class: &str, cdudn: &str) -> Str
             )
          )),
          "track" => Some((
-            format!(r"x-sonos-spotify:{}?sid=12", item), 
+            format!(r"x-sonos-spotify:{}?sid={}", item, SPOTIFY_SID), 
             get_metadata(
                 &format!(r"00030020{}", item),
                 r"", 
@@ -39,7 +42,7 @@ fn get_metadata(id: &str, parent_id: &str, upnp_class: &str, cdudn: &str) -> Str
             )
          )),
          "playlist" => Some((
-            format!(r"x-rincon-cpcontainer:0006206c{}??sid=12", item), 
+            format!(r"x-rincon-cpcontainer:0006206c{}?sid={}", item, SPOTIFY_SID), 
             get_metadata(
                 &format!(r"0004206c{}", item),
                 r"", 
@@ -59,10 +62,10 @@ pub(crate) fn apple_uri_and_metadata(item: &str) -> Option<(String, String)> {
     log::debug!("Got Apple {}: {}",  kind, id);
     let item = format!("{}:{}", kind, id);
     let item = encode(&item);
-    let cdudn = format!(r"SA_RINCON{region}_X_#Svc{region}-0-Token", region="52231");
+    let cdudn = account_cdudn(service_type(APPLE_MUSIC_SID));
     match kind {
         "album" | "libraryalbum" => Some((
-            format!(r"x-rincon-cpcontainer:0004206c{}?sid=204", item), 
+            format!(r"x-rincon-cpcontainer:0004206c{}?sid={}", item, APPLE_MUSIC_SID), 
             get_metadata(
                 &format!(r"0004206c{}", item),
                 r"00020000album%3A",
@@ -71,7 +74,7 @@ pub(crate) fn apple_uri_and_metadata(item: &str) -> Option<(String, String)> {
             )
          )),
          "song" | "librarytrack" => Some((
-            format!(r"x-sonos-http:{}.mp4?sid=204", item), 
+            format!(r"x-sonos-http:{}.mp4?sid={}", item, APPLE_MUSIC_SID), 
             get_metadata(
                 &format!(r"10032020{}", item),
                 r"1004206calbum%3A", 
@@ -80,7 +83,7 @@ pub(crate) fn apple_uri_and_metadata(item: &str) -> Option<(String, String)> {
             )
          )),
          "playlist" | "libraryplaylist" => Some((
-            format!(r"x-rincon-cpcontainer:1006206c{}?sid=204", item), 
+            format!(r"x-rincon-cpcontainer:1006206c{}?sid={}", item, APPLE_MUSIC_SID), 
             get_metadata(
                 &format!(r"1006206c{}", item),
                 r"00020000playlist%3A", 
@@ -92,6 +95,141 @@ pub(crate) fn apple_uri_and_metadata(item: &str) -> Option<(String, String)> {
     }
 }
 
+pub(crate) fn library_uri_and_metadata(content: &Content) -> Option<(String, String)> {
+    log::debug!("Got library {}: {}", content.class()?, content.id());
+    Some((
+        content.uri()?.into(),
+        get_metadata(
+            content.id(),
+            r"",
+            content.class()?,
+            r"RINCON_AssociatedZPUDN"
+        )
+    ))
+}
+
+/// Uri schemes of radio streams, which Sonos plays directly instead of through the queue.
+const RADIO_SCHEMES: &[&str] = &[
+    "x-sonosapi-stream:",
+    "x-sonosapi-radio:",
+    "x-sonosapi-hls:",
+    "x-rincon-mp3radio:",
+    "aac:",
+];
+
+/// Extensions of audio files that can be played from plain http urls.
+const AUDIO_FILE_EXTENSIONS: &[&str] = &[
+    "mp3", "m4a", "mp4", "aac", "flac", "ogg", "oga", "wav", "wma", "aif", "aiff",
+];
+
+/// Mime types of urls without a file extension that are played as a stream.
+const STREAM_MIME_TYPES: &[&str] = &[
+    "audio/mpeg",
+    "audio/aac",
+    "audio/aacp",
+    "audio/mpegurl",
+    "audio/x-mpegurl",
+    "audio/x-scpls",
+    "application/vnd.apple.mpegurl",
+];
+
+/// Escape a uri and its metadata for use as SOAP arguments, which `args!` passes as is.
+/// Radio uris like `x-sonosapi-stream:s17077?sid=254&flags=8224&sn=0` contain `&`.
+pub(crate) fn escape_uri_and_metadata(uri: &str, metadata: &str) -> (String, String) {
+    (escape_str_pcdata(uri).into_owned(), escape_str_pcdata(metadata).into_owned())
+}
+
+pub(crate) fn is_radio_uri(uri: &str) -> bool {
+    RADIO_SCHEMES.iter().any(|scheme| uri.starts_with(scheme))
+}
+
+/// Turn a stream url into one Sonos plays as radio, which older players accept even without
+/// a file extension.
+fn radio_uri(url: &str) -> Option<String> {
+    if is_radio_uri(url) {
+        return Some(url.to_string());
+    }
+    let (_scheme, rest) = url.split_once(':')?;
+    Some(format!(r"x-rincon-mp3radio:{}", rest))
+}
+
+fn file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Urls of audio files are played as files, everything else is a stream unless the mime type
+/// says otherwise.
+fn is_stream_url(url: &str, mime: Option<&str>) -> bool {
+    if is_radio_uri(url) {
+        return true;
+    }
+    let extension = file_name(url).rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+    if matches!(extension, Some(ext) if AUDIO_FILE_EXTENSIONS.contains(&ext.as_str())) {
+        return false;
+    }
+    match mime {
+        Some(mime) => STREAM_MIME_TYPES.contains(&mime.to_ascii_lowercase().as_str()),
+        None => true,
+    }
+}
+
+fn get_radio_metadata(id: &str, parent_id: &str, title: &str) -> String {
+    let mut item = DidlObject::item(id, parent_id);
+    item.set_title(title)
+        .set_class(r"object.item.audioItem.audioBroadcast")
+        .add_desc(DidlDesc::new("cdudn", RINCON_NAMESPACE, anonymous_cdudn(service_type(TUNEIN_SID))));
+    DidlLite::from(item).to_string()
+}
+
+pub(crate) fn radio_stream_uri_and_metadata(url: &str) -> Option<(String, String)> {
+    log::debug!("Got radio stream {}", url);
+    Some((radio_uri(url)?, get_radio_metadata(r"R:0/0/0", r"R:0/0", url)))
+}
+
+pub(crate) fn url_uri_and_metadata(url: &str, hints: &TrackHints) -> Option<(String, String)> {
+    let stream = is_stream_url(url, hints.mime.as_deref());
+    log::debug!("Got {} url {}", if stream { "stream" } else { "file" }, url);
+    let (uri, class) = if stream {
+        (radio_uri(url)?, r"object.item.audioItem.audioBroadcast")
+    } else {
+        (url.to_string(), r"object.item.audioItem.musicTrack")
+    };
+
+    let title = match &hints.title {
+        Some(title) => title.as_str(),
+        None if stream => url,
+        None => file_name(url),
+    };
+    let mut item = DidlObject::item(r"-1", r"-1");
+    let mut res = DidlResource::new(uri.as_str());
+    res.set_attribute("protocolInfo", format!(r"http-get:*:{}:*", hints.mime.as_deref().unwrap_or("*")));
+    item.add_resource(res);
+    item.set_title(title).set_class(class);
+    if let Some(artist) = &hints.artist {
+        item.set_property("dc:creator", artist.as_str());
+    }
+    if let Some(album) = &hints.album {
+        item.set_property("upnp:album", album.as_str());
+    }
+    if let Some(art) = &hints.art {
+        item.set_property("upnp:albumArtURI", art.as_str());
+    }
+    if stream {
+        item.add_desc(DidlDesc::new("cdudn", RINCON_NAMESPACE, anonymous_cdudn(service_type(TUNEIN_SID))));
+    }
+    Some((uri, DidlLite::from(item).to_string()))
+}
+
+pub(crate) fn radio_favorite_uri_and_metadata(content: &Content) -> Option<(String, String)> {
+    log::debug!("Got radio favorite {}: {}", content.title(), content.id());
+    let metadata = match content.metadata() {
+        Some(metadata) => metadata.to_string(),
+        None => get_radio_metadata(content.id(), content.parent_id(), content.title()),
+    };
+    Some((content.uri()?.into(), metadata))
+}
+
 #[cfg(test)]
 mod tests{
     use super::*;
@@ -115,6 +253,13 @@ mod tests{
         Ok(())
     }
 
+    #[test]
+    fn test_spotify_playlist() -> Result<(), Box<dyn Error>> {
+        let (uri, _meta) = spotify_uri_and_metadata(r"playlist:37i9dQZF1DXcBWIGoYBM5M").ok_or("unable to parse item")?;
+        assert_eq!(uri, r"x-rincon-cpcontainer:0006206cspotify%3Aplaylist%3A37i9dQZF1DXcBWIGoYBM5M?sid=12");
+        Ok(())
+    }
+
     #[test]
     fn test_apple_librarytrack() -> Result<(), Box<dyn Error>> {
         let target_uri = "x-sonos-http:librarytrack%3Aa.1442979904.mp4?sid=204";
@@ -124,4 +269,53 @@ mod tests{
         assert_eq!(target_metadata, metadata);
         Ok(())
     }
+
+    #[test]
+    fn test_radio_stream() -> Result<(), Box<dyn Error>> {
+        let target_metadata = r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns:r="urn:schemas-rinconnetworks-com:metadata-1-0/" xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"><item id="R:0/0/0" restricted="true" parentID="R:0/0"><dc:title>http://stream.live.vc.bbcmedia.co.uk/bbc_radio_fourfm</dc:title><upnp:class>object.item.audioItem.audioBroadcast</upnp:class><desc id="cdudn" nameSpace="urn:schemas-rinconnetworks-com:metadata-1-0/">SA_RINCON65031_</desc></item></DIDL-Lite>"#;
+        let (uri, metadata) = radio_stream_uri_and_metadata("http://stream.live.vc.bbcmedia.co.uk/bbc_radio_fourfm").ok_or("unable to parse url")?;
+        assert_eq!(uri, "x-rincon-mp3radio://stream.live.vc.bbcmedia.co.uk/bbc_radio_fourfm");
+        assert_eq!(target_metadata, metadata);
+
+        let (uri, _) = radio_stream_uri_and_metadata("x-sonosapi-stream:s17077?sid=254&flags=8224&sn=0").ok_or("unable to parse url")?;
+        assert_eq!(uri, "x-sonosapi-stream:s17077?sid=254&flags=8224&sn=0");
+        assert!(is_radio_uri(&uri));
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_radio_uri() -> Result<(), Box<dyn Error>> {
+        let (uri, metadata) = radio_stream_uri_and_metadata("x-sonosapi-stream:s17077?sid=254&flags=8224&sn=0").ok_or("unable to parse url")?;
+        let (uri, metadata) = escape_uri_and_metadata(&uri, &metadata);
+        assert_eq!(uri, "x-sonosapi-stream:s17077?sid=254&amp;flags=8224&amp;sn=0");
+        assert!(metadata.starts_with("&lt;DIDL-Lite"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_url() -> Result<(), Box<dyn Error>> {
+        let hints = TrackHints { title: Some("Feeling Good".into()), artist: Some("Nina Simone".into()), ..Default::default() };
+        let target_metadata = r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns:r="urn:schemas-rinconnetworks-com:metadata-1-0/" xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"><item id="-1" restricted="true" parentID="-1"><res protocolInfo="http-get:*:*:*">http://nas.local/music/feeling_good.flac</res><dc:title>Feeling Good</dc:title><upnp:class>object.item.audioItem.musicTrack</upnp:class><dc:creator>Nina Simone</dc:creator></item></DIDL-Lite>"#;
+        let (uri, metadata) = url_uri_and_metadata("http://nas.local/music/feeling_good.flac", &hints).ok_or("unable to parse url")?;
+        assert_eq!(uri, "http://nas.local/music/feeling_good.flac");
+        assert_eq!(target_metadata, metadata);
+
+        let (uri, _) = url_uri_and_metadata("http://icecast.local:8000/live", &TrackHints::default()).ok_or("unable to parse url")?;
+        assert_eq!(uri, "x-rincon-mp3radio://icecast.local:8000/live");
+
+        let hints = TrackHints { mime: Some("audio/flac".into()), ..Default::default() };
+        let (uri, _) = url_uri_and_metadata("http://nas.local/stream?id=42", &hints).ok_or("unable to parse url")?;
+        assert_eq!(uri, "http://nas.local/stream?id=42");
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_url() -> Result<(), Box<dyn Error>> {
+        let (uri, metadata) = url_uri_and_metadata("http://nas.local/song.mp3?token=a&sig=b", &TrackHints::default()).ok_or("unable to parse url")?;
+        assert_eq!(uri, "http://nas.local/song.mp3?token=a&sig=b");
+        let (uri, metadata) = escape_uri_and_metadata(&uri, &metadata);
+        assert_eq!(uri, "http://nas.local/song.mp3?token=a&amp;sig=b");
+        assert!(metadata.contains("song.mp3?token=a&amp;amp;sig=b&lt;/res>"));
+        Ok(())
+    }
 }
\ No newline at end of file
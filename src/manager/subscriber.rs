@@ -1,7 +1,8 @@
 #![allow(missing_docs)]
 
-use crate::speaker::{
-    extract_av_transport_last_change, extract_zone_topology, AV_TRANSPORT,
+use crate::{
+    speaker::{extract_av_transport_last_change, AV_TRANSPORT},
+    ZoneGroupState,
 };
 
 use futures_util::stream::StreamExt;
@@ -102,7 +103,7 @@ impl Subscriber {
                             "ZoneGroupTopology" => {
                                 state_vars
                                     .remove("ZoneGroupState")
-                                    .and_then(|xml| extract_zone_topology(&xml)
+                                    .and_then(|xml| ZoneGroupState::from_xml(&xml)
                                         .map_err(|err| warn!("Unable to extract topology: {}", err))
                                         .ok())
                                     .and_then(|topology| tx.send(TopoUpdate(uuid.clone(), topology)).ok());
@@ -113,7 +114,7 @@ impl Subscriber {
                                     .and_then(|xml| extract_av_transport_last_change(&xml)
                                         .map_err(|err| warn!("Unable to extract last change: {}", err))
                                         .ok())
-                                    .and_then(|last_change| tx.send(AVTransUpdate(uuid.clone(), last_change)).ok());
+                                    .and_then(|last_change| tx.send(AVTransUpdate(uuid.clone(), last_change.into())).ok());
                             }
                             _ => ()
 
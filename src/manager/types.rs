@@ -1,12 +1,14 @@
+use std::time::Duration;
 use tokio::sync::{mpsc, oneshot};
 
-use crate::{Snapshot, SpeakerInfo, Track, URN};
+use crate::{Snapshot, Track, TransportState, ZoneGroupState, URN};
 
 use super::{Error, ZoneAction};
 
 #[derive(Debug)]
 pub(super) enum Command {
     DoZoneAction(Responder, ZoneName, ZoneAction),
+    GetTopology(Responder),
     // Browse or search media
     // Subscribe to events
     // Management of controller?
@@ -16,8 +18,12 @@ pub(super) enum Command {
 pub enum Response {
     Ok(()),
     NotOk,
-    Snapshot(Snapshot),
-    Queue(Vec<Track>)
+    Snapshot(Box<Snapshot>),
+    Queue(Vec<Track>),
+    Volume(u32),
+    Mute(bool),
+    SleepTimer(Option<Duration>),
+    Topology(ZoneGroupState),
 }
 
 #[derive(Debug, Clone)]
@@ -28,13 +34,32 @@ pub(super) enum Event {
     NoOp,
 }
 
+/// The AV transport state of a speaker as reported by its `LastChange` events.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AVStatus {
+    pub(crate) transport_state: Option<TransportState>,
+    pub(crate) vars: Vec<(String, String)>,
+}
+
+impl From<Vec<(String, String)>> for AVStatus {
+    fn from(vars: Vec<(String, String)>) -> Self {
+        let transport_state = vars
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("TransportState"))
+            .map(|(_, v)| TransportState::from(v.as_str()));
+        AVStatus {
+            transport_state,
+            vars,
+        }
+    }
+}
+
 pub(crate) type Uuid = String;
 pub(super) type CmdSender = mpsc::Sender<Command>;
 pub(super) type EventReceiver = tokio::sync::watch::Receiver<Event>;
 
 pub(super) type ReducedTopology = Vec<(Uuid, Vec<Uuid>)>;
-pub(super) type Topology = Vec<(Uuid, Vec<SpeakerInfo>)>;
-pub(super) type AVStatus = Vec<(String, String)>;
+pub(super) type Topology = ZoneGroupState;
 pub(super) type Result<T, E = Error> = std::result::Result<T, E>;
 
 /// Type for zone name
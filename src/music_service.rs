@@ -0,0 +1,157 @@
+use crate::{utils, Result};
+use roxmltree::{Document, Node};
+
+/// How a music service authenticates its users, from the `Policy` of its descriptor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthPolicy {
+    /// No account needed, e.g. TuneIn.
+    Anonymous,
+    /// The user logs in with a user name and password.
+    UserId,
+    /// The user links the household on the website of the service.
+    DeviceLink,
+    /// The user links the household in the app of the service.
+    AppLink,
+    /// A policy this crate doesn't know about.
+    Other(String),
+}
+
+impl From<&str> for AuthPolicy {
+    fn from(s: &str) -> Self {
+        match s {
+            "Anonymous" => AuthPolicy::Anonymous,
+            "UserId" => AuthPolicy::UserId,
+            "DeviceLink" => AuthPolicy::DeviceLink,
+            "AppLink" => AuthPolicy::AppLink,
+            other => AuthPolicy::Other(other.to_string()),
+        }
+    }
+}
+
+/// A music service like Spotify or TuneIn as listed by
+/// [music_services](struct.Speaker.html#method.music_services).
+/// The [sid](#method.sid), [service_type](#method.service_type) and [cdudn](#method.cdudn) are
+/// what Sonos expects in the URIs and metadata of the content of the service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MusicService {
+    id: u32,
+    name: String,
+    capabilities: u32,
+    auth_policy: AuthPolicy,
+    uri: String,
+    secure_uri: Option<String>,
+}
+
+#[allow(missing_docs)]
+impl MusicService {
+    pub(crate) fn from_xml(node: Node<'_, '_>) -> Result<Self> {
+        let number = |attr| -> Result<u32> {
+            utils::find_node_attribute(node, attr)?
+                .parse()
+                .map_err(|e| rupnp::Error::invalid_response(e).into())
+        };
+        let auth_policy = node
+            .children()
+            .find(|c| c.tag_name().name() == "Policy")
+            .and_then(|policy| policy.attribute("Auth"))
+            .map_or(AuthPolicy::Anonymous, AuthPolicy::from);
+
+        Ok(Self {
+            id: number("Id")?,
+            name: utils::find_node_attribute(node, "Name")?.to_string(),
+            capabilities: number("Capabilities")?,
+            auth_policy,
+            uri: utils::find_node_attribute(node, "Uri")?.to_string(),
+            secure_uri: node
+                .attribute("SecureUri")
+                .filter(|uri| !uri.is_empty())
+                .map(str::to_string),
+        })
+    }
+
+    /// Parses the `AvailableServiceDescriptorList` of `ListAvailableServices`.
+    pub(crate) fn from_descriptor_list(descriptor_list: &str) -> Result<Vec<Self>> {
+        let document = Document::parse(descriptor_list)?;
+        utils::find_root_node(&document, "Services", "DescriptorList")?
+            .children()
+            .filter(|c| c.tag_name().name() == "Service")
+            .map(Self::from_xml)
+            .collect()
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// A bitmask of what the service supports, e.g. search or favorites.
+    pub fn capabilities(&self) -> u32 {
+        self.capabilities
+    }
+    pub fn auth_policy(&self) -> &AuthPolicy {
+        &self.auth_policy
+    }
+    /// The endpoint of the SMAPI web service.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+    /// The HTTPS endpoint of the SMAPI web service, if there is one.
+    pub fn secure_uri(&self) -> Option<&str> {
+        self.secure_uri.as_deref()
+    }
+    /// The service id used in the `sid` query parameter of URIs, which is just the id.
+    pub fn sid(&self) -> u32 {
+        self.id
+    }
+    /// The service type, as in the `AvailableServiceTypeList` and the `cdudn` of metadata.
+    pub fn service_type(&self) -> u32 {
+        service_type(self.id)
+    }
+    /// The value of the `cdudn` desc of metadata for content of this service.
+    pub fn cdudn(&self) -> String {
+        match self.auth_policy {
+            AuthPolicy::Anonymous => anonymous_cdudn(self.service_type()),
+            _ => account_cdudn(self.service_type()),
+        }
+    }
+}
+
+pub(crate) fn service_type(id: u32) -> u32 {
+    id * 256 + 7
+}
+
+pub(crate) fn anonymous_cdudn(service_type: u32) -> String {
+    format!("SA_RINCON{}_", service_type)
+}
+
+pub(crate) fn account_cdudn(service_type: u32) -> String {
+    format!("SA_RINCON{st}_X_#Svc{st}-0-Token", st = service_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESCRIPTOR_LIST: &str = r#"<Services SchemaVersion="1"><Service Capabilities="2563" Id="12" MaxMessagingChars="0" Name="Spotify" SecureUri="https://spotify-v5.ws.sonos.com/smapi" Uri="https://spotify-v5.ws.sonos.com/smapi" Version="1.1" ContainerType="MService"><Policy Auth="AppLink" PollInterval="30"/><Presentation><Strings Version="1" Uri="https://spotify.ws.sonos.com/strings.xml"/></Presentation></Service><Service Capabilities="19" Id="254" MaxMessagingChars="0" Name="TuneIn" SecureUri="" Uri="http://legato.radiotime.com/Radio.asmx" Version="1.1" ContainerType="MService"><Policy Auth="Anonymous" PollInterval="0"/></Service></Services>"#;
+
+    #[test]
+    fn test_parse_music_services() -> Result<()> {
+        let services = MusicService::from_descriptor_list(DESCRIPTOR_LIST)?;
+        assert_eq!(services.len(), 2);
+
+        let spotify = &services[0];
+        assert_eq!(spotify.name(), "Spotify");
+        assert_eq!(spotify.sid(), 12);
+        assert_eq!(spotify.capabilities(), 2563);
+        assert_eq!(spotify.auth_policy(), &AuthPolicy::AppLink);
+        assert_eq!(spotify.service_type(), 3079);
+        assert_eq!(spotify.cdudn(), "SA_RINCON3079_X_#Svc3079-0-Token");
+
+        let tune_in = &services[1];
+        assert_eq!(tune_in.secure_uri(), None);
+        assert_eq!(tune_in.service_type(), 65031);
+        assert_eq!(tune_in.cdudn(), "SA_RINCON65031_");
+        Ok(())
+    }
+}
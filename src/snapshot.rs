@@ -1,5 +1,5 @@
-use crate::{track::TrackInfo, Result, Speaker};
-use futures_util::future::{try_join, try_join4};
+use crate::{manager::metadata::escape_uri_and_metadata, track::TrackInfo, utils, Result, Speaker};
+use futures_util::future::try_join4;
 
 /// A Snapshot of the state the speaker is in right now.
 /// Useful for announcing some clip at a lower volume, then later resume where you left off.
@@ -11,6 +11,7 @@ pub struct Snapshot {
     track_info: Option<TrackInfo>,
 
     transport_uri: Option<String>,
+    transport_metadata: String,
 }
 
 impl Snapshot {
@@ -38,12 +39,18 @@ impl Snapshot {
         self
     }
 
+    /// Specifies the DIDL metadata of the transport uri
+    pub fn set_transport_metadata(&mut self, transport_metadata: impl Into<String>) -> &mut Self {
+        self.transport_metadata = transport_metadata.into();
+        self
+    }
+
     pub(crate) async fn from_speaker(speaker: &Speaker) -> Result<Self> {
-        let (volume, track_info, is_playing, transport_uri) = try_join4(
+        let (volume, track_info, is_playing, media_info) = try_join4(
             speaker.volume(),
             speaker.track(),
             speaker.is_playing(),
-            speaker.transport_uri(),
+            speaker.media_info(),
         )
         .await?;
 
@@ -51,7 +58,8 @@ impl Snapshot {
             volume: Some(volume),
             track_info,
             is_playing: Some(is_playing),
-            transport_uri,
+            transport_uri: media_info.current_uri,
+            transport_metadata: media_info.current_uri_metadata,
         })
     }
 
@@ -64,16 +72,21 @@ impl Snapshot {
             Some(uri) if uri.starts_with("x-sonos-vli") => {
                 log::warn!("unsupported transport uri: 'x-sonos-vli:...'")
             }
-            Some(uri) => speaker.set_transport_uri(uri, "").await?,
+            Some(uri) => {
+                let (uri, metadata) = escape_uri_and_metadata(uri, &self.transport_metadata);
+                speaker.set_transport_uri(&uri, &metadata).await?
+            }
             None => {}
         }
 
-        if let Some(track_info) = &self.track_info {
-            try_join(
-                speaker.seek_track(track_info.track_no()),
-                speaker.skip_to(track_info.elapsed()),
-            )
-            .await?;
+        // Line-in and TV inputs are live streams without tracks to seek to
+        let is_stream = matches!(self.transport_uri.as_deref(), Some(uri) if utils::is_input_stream_uri(uri));
+
+        if let Some(track_info) = self.track_info.as_ref().filter(|_| !is_stream) {
+            speaker.seek_track(track_info.track_no()).await?;
+            if let Some(elapsed) = track_info.elapsed() {
+                speaker.skip_to(elapsed.as_secs() as u32).await?;
+            }
         }
 
         match self.is_playing {
@@ -1,24 +1,43 @@
-use crate::{Error, RepeatMode, Result, Snapshot, SpeakerInfo, args, content::Content, track::{Track, TrackInfo}, utils::{self, HashMapExt}};
+use crate::{AlbumArt, AlbumArtCache, EnqueuedTracks, EqType, Error, MediaInfo, MusicService, RepeatMode, Result, SurroundMode, Snapshot, SpeakerInfo, TransportInfo, TransportState, ZoneAttributes, ZoneInfo, album_art, alarm::{self, Alarm}, args, topology::{ChannelMap, ZoneGroup, ZoneGroupMember, ZoneGroupState}, content::{BrowseResult, Content, LibraryCategory}, datatypes, track::{PositionInfo, Track, TrackHints, TrackInfo}, utils::{self, HashMapExt}};
 
+use crate::manager::{escape::escape_str_pcdata, metadata};
 use roxmltree::{Document, Node};
 use rupnp::{ssdp::URN, Device};
-use std::{collections::HashMap, net::Ipv4Addr};
+use futures_util::{
+    future::Future,
+    stream::{self, Stream, TryStreamExt},
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    net::Ipv4Addr,
+    time::Duration,
+};
 
 pub(crate) const SONOS_URN: URN = URN::device("schemas-upnp-org", "ZonePlayer", 1);
 
 pub(crate) const AV_TRANSPORT: &URN = &URN::service("schemas-upnp-org", "AVTransport", 1);
 const DEVICE_PROPERTIES: &URN = &URN::service("schemas-upnp-org", "DeviceProperties", 1);
 const RENDERING_CONTROL: &URN = &URN::service("schemas-upnp-org", "RenderingControl", 1);
+const GROUP_RENDERING_CONTROL: &URN =
+    &URN::service("schemas-upnp-org", "GroupRenderingControl", 1);
 const CONTENT_DIRECTORY: &URN = &URN::service("schemas-upnp-org", "ContentDirectory", 1);
 pub(crate) const ZONE_GROUP_TOPOLOGY: &URN =
     &URN::service("schemas-upnp-org", "ZoneGroupTopology", 1);
 const QUEUE: &URN = &URN::service("schemas-sonos-com", "Queue", 1);
 const MUSIC_SERVICES: &URN = &URN::service("schemas-upnp-org", "MusicServices", 1);
+const ALARM_CLOCK: &URN = &URN::service("schemas-upnp-org", "AlarmClock", 1);
+const AUDIO_IN: &URN = &URN::service("schemas-upnp-org", "AudioIn", 1);
 
 pub(crate) const EXTRA_DEVICE_FIELDS: &[&str; 2] = &["roomName", "UDN"];
 
 const DEFAULT_ARGS: &str = "<InstanceID>0</InstanceID>";
 
+/// The maximum number of URIs `AddMultipleURIsToQueue` accepts per request.
+const MAX_URIS_PER_ENQUEUE: usize = 16;
+
+/// The number of items fetched per request by the streaming browse methods.
+const PAGE_SIZE: u32 = 100;
+
 #[derive(Debug, Clone)]
 /// A sonos speaker, wrapping a UPnP-Device and providing user-oriented methods in an asynyronous
 /// API.
@@ -41,6 +60,8 @@ impl Speaker {
                 name,
                 uuid,
                 location,
+                channel_map: None,
+                ht_sat_chan_map: None,
             };
             Some(Self { device, info })
         } else {
@@ -90,6 +111,86 @@ impl Speaker {
         &self.info.uuid
     }
 
+    // DEVICE_PROPERTIES
+
+    pub async fn zone_info(&self) -> Result<ZoneInfo> {
+        let mut map = self.action(DEVICE_PROPERTIES, "GetZoneInfo", "").await?;
+
+        Ok(ZoneInfo {
+            serial_number: map.extract("SerialNumber")?,
+            software_version: map.extract("SoftwareVersion")?,
+            display_software_version: map.extract("DisplaySoftwareVersion")?,
+            hardware_version: map.extract("HardwareVersion")?,
+            ip_address: map
+                .extract("IPAddress")?
+                .parse()
+                .map_err(rupnp::Error::invalid_response)?,
+            mac_address: map.extract("MACAddress")?,
+            extra_info: map.extract("ExtraInfo")?,
+        })
+    }
+
+    pub async fn zone_attributes(&self) -> Result<ZoneAttributes> {
+        let mut map = self
+            .action(DEVICE_PROPERTIES, "GetZoneAttributes", "")
+            .await?;
+
+        Ok(ZoneAttributes {
+            name: map.extract("CurrentZoneName")?,
+            icon: map.extract("CurrentIcon")?,
+            configuration: map.extract("CurrentConfiguration")?,
+        })
+    }
+
+    /// Renames the room and changes its icon, e.g. to `x-rincon-roomicon:living`.
+    /// The current icon is kept when `icon` is `None`.
+    pub async fn rename(&mut self, new_name: &str, icon: Option<&str>) -> Result<()> {
+        let attributes = self.zone_attributes().await?;
+        let args = args! {
+            "DesiredZoneName": escape_str_pcdata(new_name),
+            "DesiredIcon": icon.unwrap_or(&attributes.icon),
+            "DesiredConfiguration": attributes.configuration
+        };
+        self.action(DEVICE_PROPERTIES, "SetZoneAttributes", args)
+            .await?;
+        self.info.name = new_name.to_string();
+        Ok(())
+    }
+
+    pub async fn household_id(&self) -> Result<String> {
+        self.action(DEVICE_PROPERTIES, "GetHouseholdID", "")
+            .await?
+            .extract("CurrentHouseholdID")
+    }
+
+    /// Whether the white status light is on.
+    pub async fn led_state(&self) -> Result<bool> {
+        self.action(DEVICE_PROPERTIES, "GetLEDState", "")
+            .await?
+            .extract("CurrentLEDState")
+            .and_then(utils::parse_on_off)
+    }
+    pub async fn set_led_state(&self, on: bool) -> Result<()> {
+        let args = args! { "DesiredLEDState": if on { "On" } else { "Off" } };
+        self.action(DEVICE_PROPERTIES, "SetLEDState", args)
+            .await
+            .map(drop)
+    }
+
+    /// Whether the buttons on the speaker are locked.
+    pub async fn button_lock(&self) -> Result<bool> {
+        self.action(DEVICE_PROPERTIES, "GetButtonLockState", "")
+            .await?
+            .extract("CurrentButtonLockState")
+            .and_then(utils::parse_on_off)
+    }
+    pub async fn set_button_lock(&self, locked: bool) -> Result<()> {
+        let args = args! { "DesiredButtonLockState": if locked { "On" } else { "Off" } };
+        self.action(DEVICE_PROPERTIES, "SetButtonLockState", args)
+            .await
+            .map(drop)
+    }
+
     // AV_TRANSPORT
     pub async fn stop(&self) -> Result<()> {
         self.action(AV_TRANSPORT, "Stop", DEFAULT_ARGS)
@@ -111,11 +212,11 @@ impl Speaker {
         }
     }
 
+    /// Pauses the speaker if it is playing or about to play, and starts playback otherwise.
     pub async fn play_or_pause(&self) -> Result<()> {
-        if self.is_playing().await? {
-            self.pause().await
-        } else {
-            self.play().await
+        match self.transport_info().await?.state() {
+            TransportState::Playing | TransportState::Transitioning => self.pause().await,
+            _ => self.play().await,
         }
     }
 
@@ -150,17 +251,7 @@ impl Speaker {
             .await?
             .extract("PlayMode")?;
 
-        match play_mode.to_uppercase().as_str() {
-            "NORMAL" => Ok((RepeatMode::None, false)),
-            "REPEAT_ALL" => Ok((RepeatMode::All, false)),
-            "REPEAT_ONE" => Ok((RepeatMode::One, false)),
-            "SHUFFLE_NOREPEAT" => Ok((RepeatMode::None, true)),
-            "SHUFFLE" => Ok((RepeatMode::All, true)),
-            "SHUFFLE_REPEAT_ONE" => Ok((RepeatMode::One, true)),
-            _ => Err(Error::UPnP(rupnp::Error::invalid_response(
-                crate::datatypes::ParseRepeatModeError,
-            ))),
-        }
+        datatypes::parse_play_mode(&play_mode)
     }
     pub async fn repeat_mode(&self) -> Result<RepeatMode> {
         self.playback_mode()
@@ -172,14 +263,7 @@ impl Speaker {
     }
 
     pub async fn set_playback_mode(&self, repeat_mode: RepeatMode, shuffle: bool) -> Result<()> {
-        let playback_mode = match (repeat_mode, shuffle) {
-            (RepeatMode::None, false) => "NORMAL",
-            (RepeatMode::One, false) => "REPEAT_ONE",
-            (RepeatMode::All, false) => "REPEAT_ALL",
-            (RepeatMode::None, true) => "SHUFFLE_NOREPEAT",
-            (RepeatMode::One, true) => "SHUFFLE_REPEAT_ONE",
-            (RepeatMode::All, true) => "SHUFFLE",
-        };
+        let playback_mode = datatypes::play_mode_str(repeat_mode, shuffle);
         self.action(
             AV_TRANSPORT,
             "SetPlayMode",
@@ -210,47 +294,57 @@ impl Speaker {
             .map(drop)
     }
 
-    pub async fn is_playing(&self) -> Result<bool> {
-        self.action(AV_TRANSPORT, "GetTransportInfo", DEFAULT_ARGS)
+    /// Stops playback after the given duration. `None` cancels a running sleep timer.
+    pub async fn set_sleep_timer(&self, duration: Option<Duration>) -> Result<()> {
+        let duration = duration
+            .map(|duration| utils::seconds_to_str(duration.as_secs() as i64))
+            .unwrap_or_default();
+        let args = args! { "InstanceID": 0, "NewSleepTimerDuration": duration };
+        self.action(AV_TRANSPORT, "ConfigureSleepTimer", args)
+            .await
+            .map(drop)
+    }
+    /// Returns `None` when no sleep timer is running.
+    pub async fn remaining_sleep_timer(&self) -> Result<Option<Duration>> {
+        let remaining = self
+            .action(AV_TRANSPORT, "GetRemainingSleepTimerDuration", DEFAULT_ARGS)
             .await?
-            .extract("CurrentTransportState")
-            .map(|x| x.eq_ignore_ascii_case("playing"))
+            .extract("RemainingSleepTimerDuration")?;
+
+        utils::duration_from_str(&remaining)
     }
 
-    pub async fn track(&self) -> Result<Option<TrackInfo>> {
+    pub async fn is_playing(&self) -> Result<bool> {
+        Ok(self.transport_info().await?.state() == &TransportState::Playing)
+    }
+
+    pub async fn transport_info(&self) -> Result<TransportInfo> {
         let mut map = self
-            .action(AV_TRANSPORT, "GetPositionInfo", DEFAULT_ARGS)
+            .action(AV_TRANSPORT, "GetTransportInfo", DEFAULT_ARGS)
             .await?;
 
-        let track_no: u32 = map.extract("Track")?.parse().unwrap();
-        let duration = map
-            .extract("TrackDuration")
-            .unwrap_or_else(|_| "0:0:0".into());
-        let elapsed = map.extract("RelTime").unwrap_or_else(|_| "0:0:0".into());
-
-        // e.g. speaker was playing spotify, then spotify disconnected but sonos is still on
-        // "x-sonos-vli"
-        if duration.eq_ignore_ascii_case("not_implemented")
-            || elapsed.eq_ignore_ascii_case("not_implemented")
-        {
-            return Ok(None);
-        }
-
-        let metadata = match map.remove("TrackMetaData") {
-            Some(metadata) => metadata,
-            None => return Ok(None),
-        };
+        Ok(TransportInfo {
+            state: TransportState::from(map.extract("CurrentTransportState")?.as_str()),
+            status: map.extract("CurrentTransportStatus")?,
+            speed: map.extract("CurrentSpeed")?,
+        })
+    }
 
-        let duration = utils::seconds_from_str(&duration)?;
-        let elapsed = utils::seconds_from_str(&elapsed)?;
+    pub async fn track(&self) -> Result<Option<TrackInfo>> {
+        Ok(self.position_info().await?.into_track_info())
+    }
 
-        let doc = Document::parse(&metadata)?;
-        let item = utils::find_root_node(&doc, "item", "Track Metadata")?;
-        let track = Track::from_xml(item)?;
+    /// Get the current track along with the position in it.
+    pub async fn position_info(&self) -> Result<PositionInfo> {
+        let mut map = self
+            .action(AV_TRANSPORT, "GetPositionInfo", DEFAULT_ARGS)
+            .await?;
 
-        Ok(Some(TrackInfo::new(
-            track, metadata, track_no, duration, elapsed,
-        )))
+        let mut info = PositionInfo::from_map(&mut map)?;
+        info.track = info
+            .track
+            .map(|track| track.resolve_album_art(self.device.url()));
+        Ok(info)
     }
 
     // RENDERING_CONTROL
@@ -344,22 +438,163 @@ impl Speaker {
             .map(drop)
     }
 
+    /// Get one of the extended EQ settings as the raw value the speaker reports.
+    /// Returns `Error::Unsupported` when the speaker doesn't have that setting.
+    pub async fn eq(&self, eq_type: EqType) -> Result<i16> {
+        let args = args! { "InstanceID": 0, "EQType": eq_type };
+        self.action(RENDERING_CONTROL, "GetEQ", args)
+            .await
+            .map_err(|err| unsupported_eq(err, eq_type))?
+            .extract("CurrentValue")
+            .and_then(|x| {
+                x.parse()
+                    .map_err(|e| rupnp::Error::invalid_response(e).into())
+            })
+    }
+    /// Set one of the extended EQ settings to a raw value.
+    /// Returns `Error::Unsupported` when the speaker doesn't have that setting.
+    pub async fn set_eq(&self, eq_type: EqType, value: i16) -> Result<()> {
+        let args = args! { "InstanceID": 0, "EQType": eq_type, "DesiredValue": value };
+        self.action(RENDERING_CONTROL, "SetEQ", args)
+            .await
+            .map(drop)
+            .map_err(|err| unsupported_eq(err, eq_type))
+    }
+
+    pub async fn night_mode(&self) -> Result<bool> {
+        self.eq(EqType::NightMode).await.map(|x| x != 0)
+    }
+    pub async fn set_night_mode(&self, night_mode: bool) -> Result<()> {
+        self.set_eq(EqType::NightMode, night_mode as i16).await
+    }
+    pub async fn speech_enhancement(&self) -> Result<bool> {
+        self.eq(EqType::DialogLevel).await.map(|x| x != 0)
+    }
+    pub async fn set_speech_enhancement(&self, speech_enhancement: bool) -> Result<()> {
+        self.set_eq(EqType::DialogLevel, speech_enhancement as i16)
+            .await
+    }
+    pub async fn sub_gain(&self) -> Result<i8> {
+        self.eq(EqType::SubGain).await.map(|x| x as i8)
+    }
+    pub async fn set_sub_gain(&self, gain: i8) -> Result<()> {
+        self.set_eq(EqType::SubGain, gain.into()).await
+    }
+    pub async fn sub_enabled(&self) -> Result<bool> {
+        self.eq(EqType::SubEnabled).await.map(|x| x != 0)
+    }
+    pub async fn set_sub_enabled(&self, enabled: bool) -> Result<()> {
+        self.set_eq(EqType::SubEnabled, enabled as i16).await
+    }
+    pub async fn surround_level(&self) -> Result<i8> {
+        self.eq(EqType::SurroundLevel).await.map(|x| x as i8)
+    }
+    pub async fn set_surround_level(&self, level: i8) -> Result<()> {
+        self.set_eq(EqType::SurroundLevel, level.into()).await
+    }
+    pub async fn surround_enabled(&self) -> Result<bool> {
+        self.eq(EqType::SurroundEnabled).await.map(|x| x != 0)
+    }
+    pub async fn set_surround_enabled(&self, enabled: bool) -> Result<()> {
+        self.set_eq(EqType::SurroundEnabled, enabled as i16).await
+    }
+    pub async fn music_surround_level(&self) -> Result<i8> {
+        self.eq(EqType::MusicSurroundLevel).await.map(|x| x as i8)
+    }
+    pub async fn set_music_surround_level(&self, level: i8) -> Result<()> {
+        self.set_eq(EqType::MusicSurroundLevel, level.into()).await
+    }
+    pub async fn height_channel_level(&self) -> Result<i8> {
+        self.eq(EqType::HeightChannelLevel).await.map(|x| x as i8)
+    }
+    pub async fn set_height_channel_level(&self, level: i8) -> Result<()> {
+        self.set_eq(EqType::HeightChannelLevel, level.into()).await
+    }
+    pub async fn surround_mode(&self) -> Result<SurroundMode> {
+        match self.eq(EqType::SurroundMode).await? {
+            0 => Ok(SurroundMode::Ambient),
+            1 => Ok(SurroundMode::Full),
+            _ => Err(rupnp::Error::ParseError("surround mode was neither `0` nor `1`").into()),
+        }
+    }
+    pub async fn set_surround_mode(&self, mode: SurroundMode) -> Result<()> {
+        let value = match mode {
+            SurroundMode::Ambient => 0,
+            SurroundMode::Full => 1,
+        };
+        self.set_eq(EqType::SurroundMode, value).await
+    }
+
+    // GROUP_RENDERING_CONTROL
+    // These only work on the coordinator of a group.
+
+    pub async fn group_volume(&self) -> Result<u32> {
+        self.action(GROUP_RENDERING_CONTROL, "GetGroupVolume", DEFAULT_ARGS)
+            .await?
+            .extract("CurrentVolume")
+            .and_then(|x| {
+                x.parse()
+                    .map_err(|e| rupnp::Error::invalid_response(e).into())
+            })
+    }
+    /// Sets the volume of the whole group.
+    /// The volume ratios between the speakers are kept as they were at the last
+    /// [snapshot_group_volume](struct.Speaker.html#method.snapshot_group_volume).
+    pub async fn set_group_volume(&self, volume: u32) -> Result<()> {
+        let args = args! { "InstanceID": 0, "DesiredVolume": volume };
+        self.action(GROUP_RENDERING_CONTROL, "SetGroupVolume", args)
+            .await
+            .map(drop)
+    }
+    pub async fn set_relative_group_volume(&self, adjustment: i32) -> Result<u32> {
+        let args = args! { "InstanceID": 0, "Adjustment": adjustment };
+        self.action(GROUP_RENDERING_CONTROL, "SetRelativeGroupVolume", args)
+            .await?
+            .extract("NewVolume")
+            .and_then(|x| {
+                x.parse()
+                    .map_err(|e| rupnp::Error::invalid_response(e).into())
+            })
+    }
+    /// Stores the current volume ratios between the speakers of the group, which are used by
+    /// subsequent group volume changes.
+    pub async fn snapshot_group_volume(&self) -> Result<()> {
+        self.action(GROUP_RENDERING_CONTROL, "SnapshotGroupVolume", DEFAULT_ARGS)
+            .await
+            .map(drop)
+    }
+
+    pub async fn group_mute(&self) -> Result<bool> {
+        self.action(GROUP_RENDERING_CONTROL, "GetGroupMute", DEFAULT_ARGS)
+            .await?
+            .extract("CurrentMute")
+            .and_then(utils::parse_bool)
+    }
+    pub async fn set_group_mute(&self, mute: bool) -> Result<()> {
+        let args = args! { "InstanceID": 0, "DesiredMute": mute as u8 };
+        self.action(GROUP_RENDERING_CONTROL, "SetGroupMute", args)
+            .await
+            .map(drop)
+    }
+
     // Queue
     pub async fn queue(&self) -> Result<Vec<Track>> {
-        let args = args! { "QueueID": 0, "StartingIndex": 0, "RequestedCount": std::u32::MAX };
-        let result = self
-            .action(QUEUE, "Browse", args)
-            .await?
-            .extract("Result")?;
-
-        Document::parse(&result)?
-            .root()
-            .first_element_child()
-            .ok_or_else(|| rupnp::Error::ParseError("Queue Response contains no children"))?
-            .children()
-            .filter(roxmltree::Node::is_element)
-            .map(Track::from_xml)
-            .collect()
+        self.queue_stream().try_collect().await
+    }
+
+    /// Returns the tracks of the queue starting at index `start`, which starts at 0.
+    pub async fn queue_page(&self, start: u32, count: u32) -> Result<BrowseResult<Track>> {
+        let args = args! { "QueueID": 0, "StartingIndex": start, "RequestedCount": count };
+        let mut map = self.action(QUEUE, "Browse", args).await?;
+
+        BrowseResult::from_map(&mut map, |node| {
+            Track::from_xml(node).map(|track| track.resolve_album_art(self.device.url()))
+        })
+    }
+
+    /// Returns all tracks of the queue, fetching them page by page as the stream is polled.
+    pub fn queue_stream(&self) -> impl Stream<Item = Result<Track>> + '_ {
+        paged(move |start| self.queue_page(start, PAGE_SIZE))
     }
 
     // TODO test the next ones
@@ -386,24 +621,200 @@ impl Speaker {
             .map(drop)
     }
 
+    /// Enqueues several tracks at once, either at track number `position` or at the end of the
+    /// queue. The uris and metadata have to be escaped like for [queue_next](struct.Speaker.html#method.queue_next),
+    /// e.g. URLs with a query string contain `&`.
+    /// Sonos accepts at most 16 tracks per request, so longer lists are split into several requests.
+    pub async fn queue_many(
+        &self,
+        tracks: &[(String, String)],
+        position: Option<u32>,
+    ) -> Result<EnqueuedTracks> {
+        let enqueued = enqueue_in_chunks(tracks, position, |chunk, desired_track_no| {
+            let uris = chunk.iter().map(|(uri, _)| uri.as_str()).collect::<Vec<_>>();
+            let metadata = chunk.iter().map(|(_, metadata)| metadata.as_str()).collect::<Vec<_>>();
+            let args = args! { "InstanceID": 0, "UpdateID": 0, "NumberOfURIs": chunk.len(), "EnqueuedURIs": uris.join(" "), "EnqueuedURIsMetaData": metadata.join(" "), "ContainerURI": "", "ContainerMetaData": "", "DesiredFirstTrackNumberEnqueued": desired_track_no, "EnqueueAsNext": position.is_some() as u8 }.to_string();
+            async move {
+                let mut map = self
+                    .action(AV_TRANSPORT, "AddMultipleURIsToQueue", &args)
+                    .await?;
+                let mut number = |key| -> Result<u32> {
+                    map.extract(key)?
+                        .parse()
+                        .map_err(|e| rupnp::Error::invalid_response(e).into())
+                };
+                Ok(EnqueuedTracks {
+                    first_track_no: number("FirstTrackNumberEnqueued")?,
+                    num_tracks_added: number("NumTracksAdded")?,
+                    new_queue_length: number("NewQueueLength")?,
+                })
+            }
+        })
+        .await?;
+
+        match enqueued {
+            Some(enqueued) => Ok(enqueued),
+            None => Ok(EnqueuedTracks {
+                first_track_no: 0,
+                num_tracks_added: 0,
+                new_queue_length: self.queue_page(0, 1).await?.total_matches(),
+            }),
+        }
+    }
+
+    /// Returns the current update ID of the queue, which changes whenever the queue is
+    /// modified. Queue edits that take an update ID fail if it is stale.
+    pub async fn queue_update_id(&self) -> Result<u32> {
+        let args = args! { "QueueID": 0, "StartingIndex": 0, "RequestedCount": 0 };
+        self.action(QUEUE, "Browse", args)
+            .await?
+            .extract("UpdateID")
+            .and_then(|x| {
+                x.parse()
+                    .map_err(|e| rupnp::Error::invalid_response(e).into())
+            })
+    }
+
+    /// Moves `count` tracks starting at track number `start` in front of the track number
+    /// `insert_before`. Track numbers start at 1.
+    pub async fn move_tracks(
+        &self,
+        start: u32,
+        count: u32,
+        insert_before: u32,
+        update_id: u32,
+    ) -> Result<()> {
+        let args = args! { "InstanceID": 0, "StartingIndex": start, "NumberOfTracks": count, "InsertBefore": insert_before, "UpdateID": update_id };
+        self.action(AV_TRANSPORT, "ReorderTracksInQueue", args)
+            .await
+            .map(drop)
+    }
+
+    /// Removes `count` tracks starting at track number `start`. Track numbers start at 1.
+    /// Returns the new update ID of the queue.
+    pub async fn remove_track_range(&self, start: u32, count: u32, update_id: u32) -> Result<u32> {
+        let args = args! { "InstanceID": 0, "UpdateID": update_id, "StartingIndex": start, "NumberOfTracks": count };
+        self.action(AV_TRANSPORT, "RemoveTrackRangeFromQueue", args)
+            .await?
+            .extract("NewUpdateID")
+            .and_then(|x| {
+                x.parse()
+                    .map_err(|e| rupnp::Error::invalid_response(e).into())
+            })
+    }
+
     pub async fn clear_queue(&self) -> Result<()> {
         self.action(AV_TRANSPORT, "RemoveAllTracksFromQueue", DEFAULT_ARGS)
             .await
             .map(drop)
     }
 
+    // ALARM_CLOCK
+
+    /// Returns all alarms of the household.
+    pub async fn list_alarms(&self) -> Result<Vec<Alarm>> {
+        let alarm_list = self
+            .action(ALARM_CLOCK, "ListAlarms", "")
+            .await?
+            .extract("CurrentAlarmList")?;
+
+        alarm::extract_alarm_list(&alarm_list)
+    }
+
+    /// Creates a new alarm and returns the ID the speaker assigned to it.
+    pub async fn create_alarm(&self, alarm: &Alarm) -> Result<u32> {
+        self.action(ALARM_CLOCK, "CreateAlarm", &alarm.args())
+            .await?
+            .extract("AssignedID")
+            .and_then(|x| {
+                x.parse()
+                    .map_err(|e| rupnp::Error::invalid_response(e).into())
+            })
+    }
+
+    /// Overwrites the alarm with the same ID with this one.
+    /// Fails with `Error::AlarmWithoutId` for alarms which haven't been created yet.
+    pub async fn update_alarm(&self, alarm: &Alarm) -> Result<()> {
+        let id = alarm.id().ok_or(Error::AlarmWithoutId)?;
+        let args = format!("{}{}", args! { "ID": id }, alarm.args());
+        self.action(ALARM_CLOCK, "UpdateAlarm", &args)
+            .await
+            .map(drop)
+    }
+
+    pub async fn destroy_alarm(&self, id: u32) -> Result<()> {
+        self.action(ALARM_CLOCK, "DestroyAlarm", args! { "ID": id })
+            .await
+            .map(drop)
+    }
+
+    // AUDIO_IN
+
+    /// Whether the speaker has an analog line-in, e.g. a Play:5, Connect or Port.
+    pub fn has_line_in(&self) -> bool {
+        self.device.find_service(AUDIO_IN).is_some()
+    }
+
+    /// Plays the line-in of the speaker with the given UUID. This can be the speaker itself or
+    /// any other speaker in the system.
+    pub async fn play_line_in(&self, source_uuid: &str) -> Result<()> {
+        let uri = utils::line_in_uri(source_uuid);
+        self.set_transport_uri(&uri, "").await?;
+        self.play().await
+    }
+
+    /// Returns the left and right line-in level, each from `0` to `10`.
+    pub async fn line_in_level(&self) -> Result<(u32, u32)> {
+        let mut map = self.action(AUDIO_IN, "GetLineInLevel", "").await?;
+        let mut level = |key| {
+            map.extract(key).and_then(|x| {
+                x.parse()
+                    .map_err(|e| rupnp::Error::invalid_response(e).into())
+            })
+        };
+
+        Ok((
+            level("CurrentLeftLineInLevel")?,
+            level("CurrentRightLineInLevel")?,
+        ))
+    }
+    pub async fn set_line_in_level(&self, left: u32, right: u32) -> Result<()> {
+        let args = args! { "DesiredLeftLineInLevel": left, "DesiredRightLineInLevel": right };
+        self.action(AUDIO_IN, "SetLineInLevel", args)
+            .await
+            .map(drop)
+    }
+
+    /// The name of the line-in source as shown in the Sonos app, e.g. `Turntable`.
+    pub async fn audio_input_name(&self) -> Result<String> {
+        self.action(AUDIO_IN, "GetAudioInputAttributes", "")
+            .await?
+            .extract("CurrentName")
+    }
+    pub async fn set_audio_input_name(&self, name: &str) -> Result<()> {
+        let icon = self
+            .action(AUDIO_IN, "GetAudioInputAttributes", "")
+            .await?
+            .extract("CurrentIcon")?;
+        let args = args! { "DesiredName": escape_str_pcdata(name), "DesiredIcon": icon };
+        self.action(AUDIO_IN, "SetAudioInputAttributes", args)
+            .await
+            .map(drop)
+    }
+
     pub(crate) async fn _zone_group_state(&self) -> Result<Vec<(String, Vec<SpeakerInfo>)>> {
+        self.zone_group_state().await.map(|state| state.rooms())
+    }
+
+    /// Returns the full topology of the system, including bonded speakers hidden from the
+    /// room view and devices that vanished.
+    pub async fn zone_group_state(&self) -> Result<ZoneGroupState> {
         let state = self
             .action(ZONE_GROUP_TOPOLOGY, "GetZoneGroupState", "")
             .await?
             .extract("ZoneGroupState")?;
 
-        extract_zone_topology(&state)
-    }
-
-    /// Returns all groups in the system as a map from the group coordinators UUID to a list of [Speaker Info](struct.SpeakerInfo.html)s.
-    pub async fn zone_group_state(&self) -> Result<HashMap<String, Vec<SpeakerInfo>>> {
-        Ok(self._zone_group_state().await?.into_iter().collect())
+        ZoneGroupState::from_xml(&state)
     }
 
     /// Form a group with a player.
@@ -434,6 +845,41 @@ impl Speaker {
         }
     }
 
+    /// Bonds two speakers into a stereo pair. The pair shows up as a single room, which is
+    /// controlled through the left speaker.
+    pub async fn create_stereo_pair(left: &Speaker, right: &Speaker) -> Result<()> {
+        let channel_map = ChannelMap::stereo_pair(left.uuid(), right.uuid());
+        left.action(
+            DEVICE_PROPERTIES,
+            "CreateStereoPair",
+            args! { "ChannelMapSet": channel_map },
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Splits the stereo pair this speaker is the left speaker of.
+    /// Fails with `Error::NotAStereoPair` when the speaker isn't bonded.
+    pub async fn separate_stereo_pair(&self) -> Result<()> {
+        let topology = self.zone_group_state().await?;
+        let channel_map = topology
+            .groups()
+            .iter()
+            .flat_map(ZoneGroup::members)
+            .map(ZoneGroupMember::info)
+            .find(|speaker_info| speaker_info.uuid().eq_ignore_ascii_case(self.uuid()))
+            .and_then(|speaker_info| speaker_info.channel_map.clone())
+            .ok_or(Error::NotAStereoPair)?;
+
+        self.action(
+            DEVICE_PROPERTIES,
+            "SeparateStereoPair",
+            args! { "ChannelMapSet": channel_map },
+        )
+        .await
+        .map(drop)
+    }
+
     /// Leave the current group.
     /// Does nothing when the speaker already has no group.
     pub async fn leave(&self) -> Result<()> {
@@ -449,6 +895,7 @@ impl Speaker {
     /// Set the transport URI for the speaker.
     /// Note that (at least my old Play:5 gen 1 speaker) will only accept urls without
     /// '?foo=bar' query parameters that end with '.mp3' or '.wav' etc.
+    /// [play_url](struct.Speaker.html#method.play_url) takes care of that for plain HTTP URLs.
     pub async fn set_transport_uri(&self, uri: &str, metadata: &str) -> Result<()> {
         let args = args! { "InstanceID": 0, "CurrentURI": uri, "CurrentURIMetaData": metadata };
         self.action(AV_TRANSPORT, "SetAVTransportURI", args)
@@ -456,66 +903,325 @@ impl Speaker {
             .map(drop)
     }
 
+    /// Play an HTTP URL, generating the metadata from `hints`.
+    /// Streams are played directly as radio via `x-rincon-mp3radio:`, which also works on
+    /// players that reject URLs without a file extension. Files get queued after the current
+    /// track and are played from the queue.
+    pub async fn play_url(&self, url: &str, hints: &TrackHints) -> Result<()> {
+        let (uri, metadata) = metadata::url_uri_and_metadata(url, hints)
+            .ok_or(rupnp::Error::ParseError("URL without a scheme"))?;
+        let is_radio = metadata::is_radio_uri(&uri);
+        let (uri, metadata) = metadata::escape_uri_and_metadata(&uri, &metadata);
+
+        if is_radio {
+            self.set_transport_uri(&uri, &metadata).await?;
+        } else {
+            let queue_uri = format!("x-rincon-queue:{}#0", self.uuid());
+            let position = match self.transport_uri().await? {
+                Some(current) if current == queue_uri => {
+                    Some(self.position_info().await?.track_no() + 1)
+                }
+                _ => None,
+            };
+            let enqueued = self.queue_many(&[(uri, metadata)], position).await?;
+            self.set_transport_uri(&queue_uri, "").await?;
+            self.seek_track(enqueued.first_track_no).await?;
+        }
+        self.play().await
+    }
+
     /// Get the current transport URI for the speaker.
     pub async fn transport_uri(&self) -> Result<Option<String>> {
-        let uri = self
+        Ok(self.media_info().await?.current_uri)
+    }
+
+    /// Get the media loaded into the transport, including the URI and metadata of what is
+    /// playing and what is up next.
+    pub async fn media_info(&self) -> Result<MediaInfo> {
+        let mut map = self
             .action(AV_TRANSPORT, "GetMediaInfo", DEFAULT_ARGS)
-            .await?
-            .remove("CurrentURI");
-        Ok(uri)
+            .await?;
+        let non_empty = |s: String| if s.is_empty() { None } else { Some(s) };
+
+        let media_duration = utils::duration_from_str(&map.extract("MediaDuration")?)?;
+
+        Ok(MediaInfo {
+            nr_tracks: map.extract("NrTracks")?.parse().map_err(rupnp::Error::invalid_response)?,
+            media_duration,
+            current_uri: map.remove("CurrentURI").and_then(non_empty),
+            current_uri_metadata: map.remove("CurrentURIMetaData").unwrap_or_default(),
+            next_uri: map.remove("NextURI").and_then(non_empty),
+            next_uri_metadata: map.remove("NextURIMetaData").unwrap_or_default(),
+            play_medium: map.remove("PlayMedium").unwrap_or_default(),
+            record_medium: map.remove("RecordMedium").unwrap_or_default(),
+        })
+    }
+
+    /// Switches a soundbar to its TV input (HDMI ARC or optical).
+    pub async fn switch_to_tv(&self) -> Result<()> {
+        let uri = utils::tv_uri(self.uuid());
+        self.set_transport_uri(&uri, "").await
+    }
+
+    /// Whether the speaker is currently playing the audio of a TV input.
+    pub async fn is_playing_tv(&self) -> Result<bool> {
+        let uri = self.transport_uri().await?;
+        Ok(matches!(uri, Some(uri) if utils::is_tv_uri(&uri)))
+    }
+
+    // MUSIC_SERVICES
+
+    /// Returns all music services Sonos knows about.
+    pub async fn music_services(&self) -> Result<Vec<MusicService>> {
+        self.list_available_services()
+            .await
+            .map(|(services, _)| services)
     }
 
-    #[allow(unused)]
-    /// returns a map of lowercase service name to a tuple of (sid, capabilities, stype)
-    async fn music_services(&self) -> Result<(Vec<u32>, HashMap<String, (u32, u32, u32)>)> {
+    /// Returns the music services that are set up in this household.
+    pub async fn household_music_services(&self) -> Result<Vec<MusicService>> {
+        let (services, service_types) = self.list_available_services().await?;
+        Ok(services
+            .into_iter()
+            .filter(|service| service_types.contains(&service.service_type()))
+            .collect())
+    }
+
+    async fn list_available_services(&self) -> Result<(Vec<MusicService>, Vec<u32>)> {
         let mut map = self
             .action(MUSIC_SERVICES, "ListAvailableServices", "")
             .await?;
         let descriptor_list = map.extract("AvailableServiceDescriptorList")?;
         let service_type_list = map.extract("AvailableServiceTypeList")?;
 
-        let available_services: Vec<u32> = service_type_list
+        let service_types: Vec<u32> = service_type_list
             .split(',')
-            .map(|x| x.parse())
+            .filter(|x| !x.is_empty())
+            .map(|x| x.trim().parse())
             .collect::<Result<_, _>>()
             .map_err(rupnp::Error::invalid_response)?;
+        let services = MusicService::from_descriptor_list(&descriptor_list)?;
 
-        let document = Document::parse(&descriptor_list)?;
-        let services = utils::find_root_node(&document, "Services", "DescriptorList")?
-            .children()
-            .map(|node| -> Result<_> {
-                let id = utils::find_node_attribute(node, "Id")?;
-                let name = utils::find_node_attribute(node, "Name")?;
-                let capabilities = utils::find_node_attribute(node, "Capabilities")?;
-
-                let id = id.parse().map_err(rupnp::Error::invalid_response)?;
-                let capabilities = capabilities
-                    .parse()
-                    .map_err(rupnp::Error::invalid_response)?;
-                let s_type = id << (8 + 7);
-                Ok((name.to_lowercase(), (id, capabilities, s_type)))
-            })
-            .collect::<Result<_, _>>()?;
-
-        Ok((available_services, services))
+        Ok((services, service_types))
     }
 
     pub async fn browse(&self, object_id: &str, start: u32, limit: u32) -> Result<Vec<Content>> {
-        let args = args! { "ObjectID": object_id, "BrowseFlag": "BrowseDirectChildren", "StartingIndex": start, "RequestedCount": limit, "Filter" : "", "SortCriteria" : "" };
-        let result = self
-            .action(CONTENT_DIRECTORY, "Browse", args)
+        self.browse_page(object_id, start, limit)
+            .await
+            .map(BrowseResult::into_items)
+    }
+
+    /// Returns the children of a content directory object starting at index `start`.
+    pub async fn browse_page(
+        &self,
+        object_id: &str,
+        start: u32,
+        count: u32,
+    ) -> Result<BrowseResult<Content>> {
+        let args = args! { "ObjectID": object_id, "BrowseFlag": "BrowseDirectChildren", "StartingIndex": start, "RequestedCount": count, "Filter" : "", "SortCriteria" : "" };
+        let mut map = self.action(CONTENT_DIRECTORY, "Browse", args).await?;
+
+        BrowseResult::from_map(&mut map, Content::from_xml)
+    }
+
+    /// Returns all children of a content directory object, fetching them page by page as the
+    /// stream is polled.
+    pub fn browse_stream<'a>(
+        &'a self,
+        object_id: &'a str,
+    ) -> impl Stream<Item = Result<Content>> + 'a {
+        paged(move |start| self.browse_page(object_id, start, PAGE_SIZE))
+    }
+
+    /// Returns the metadata of a content directory object itself rather than its children.
+    pub async fn browse_metadata(&self, object_id: &str) -> Result<Content> {
+        self.browse_metadata_with_update_id(object_id)
+            .await
+            .map(|(content, _)| content)
+    }
+
+    /// Walks the content directory tree below `object_id` breadth first, browsing containers up
+    /// to `max_depth` levels deep. Returns every entry found along with its depth, where the
+    /// children of `object_id` have a depth of 1.
+    pub async fn walk_tree(&self, object_id: &str, max_depth: u32) -> Result<Vec<(u32, Content)>> {
+        let mut entries = Vec::new();
+        let mut pending = VecDeque::new();
+        pending.push_back((object_id.to_string(), 1));
+
+        while let Some((object_id, depth)) = pending.pop_front() {
+            if depth > max_depth {
+                continue;
+            }
+            let children: Vec<Content> = self.browse_stream(&object_id).try_collect().await?;
+            for child in children {
+                if child.is_container() {
+                    pending.push_back((child.id().to_string(), depth + 1));
+                }
+                entries.push((depth, child));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Searches the local music library for entries of a category starting with `prefix`,
+    /// e.g. albums starting with `Abbey`. An empty prefix lists the whole category.
+    pub async fn search_library(
+        &self,
+        category: LibraryCategory,
+        prefix: &str,
+        start: u32,
+        count: u32,
+    ) -> Result<BrowseResult<Content>> {
+        self.browse_page(&category.search_id(prefix), start, count)
+            .await
+    }
+
+    // Album art
+
+    /// Fetches album art, e.g. from the [album_art_uri](struct.Track.html#method.album_art_uri) of
+    /// a track. Relative paths are resolved against this speaker.
+    /// With a cache, every URI is only fetched once until it gets evicted.
+    /// Covers of music services are mostly served over `https`, which needs the `https` feature;
+    /// without it these URIs fail with [HttpsNotEnabled](enum.Error.html#variant.HttpsNotEnabled).
+    pub async fn fetch_album_art(
+        &self,
+        uri: &str,
+        cache: Option<&AlbumArtCache>,
+    ) -> Result<AlbumArt> {
+        let uri = utils::resolve_uri(self.device.url(), uri);
+        if let Some(art) = cache.and_then(|cache| cache.get(&uri)) {
+            return Ok(art);
+        }
+
+        let art = album_art::fetch(uri.parse()?).await?;
+        if let Some(cache) = cache {
+            cache.insert(uri, art.clone());
+        }
+        Ok(art)
+    }
+
+    // Music library
+
+    /// Returns the music shares the local library is indexed from.
+    pub async fn library_shares(&self) -> Result<Vec<Content>> {
+        self.browse(LibraryCategory::Shares.object_id(), 0, 0).await
+    }
+
+    /// Starts reindexing the music shares of the local library.
+    pub async fn refresh_share_index(&self) -> Result<()> {
+        let args = args! { "AlbumArtistDisplayOption": "" };
+        self.action(CONTENT_DIRECTORY, "RefreshShareIndex", args)
+            .await
+            .map(drop)
+    }
+
+    pub async fn share_index_in_progress(&self) -> Result<bool> {
+        self.action(CONTENT_DIRECTORY, "GetShareIndexInProgress", "")
+            .await?
+            .extract("IsIndexing")
+            .and_then(utils::parse_bool)
+    }
+
+    /// Polls the speaker every `poll_interval` until the share index is no longer in progress.
+    /// Wrap it in `tokio::time::timeout` to give up eventually.
+    pub async fn wait_for_share_index(&self, poll_interval: Duration) -> Result<()> {
+        loop {
+            // Reindexing takes a moment to start after it was requested, so wait first
+            tokio::time::sleep(poll_interval).await;
+            if !self.share_index_in_progress().await? {
+                return Ok(());
+            }
+        }
+    }
+
+    // Sonos playlists
+
+    /// Returns the Sonos playlists of the household.
+    pub async fn playlists(&self) -> Result<Vec<Content>> {
+        self.browse("SQ:", 0, 0).await
+    }
+
+    /// Saves the current queue as a new Sonos playlist and returns its object id.
+    pub async fn save_queue_as_playlist(&self, title: &str) -> Result<String> {
+        let args = args! { "InstanceID": 0, "Title": escape_str_pcdata(title), "ObjectID": "" };
+        self.action(AV_TRANSPORT, "SaveQueue", args)
+            .await?
+            .extract("AssignedObjectID")
+    }
+
+    /// Creates an empty Sonos playlist and returns its object id.
+    pub async fn create_playlist(&self, title: &str) -> Result<String> {
+        let args = args! { "InstanceID": 0, "Title": escape_str_pcdata(title), "EnqueuedURI": "", "EnqueuedURIMetaData": "" };
+        self.action(AV_TRANSPORT, "CreateSavedQueue", args)
             .await?
-            .extract("Result")?;
-        // log::debug!("{:#?}", result);
-    
-            Document::parse(&result)?
-                .root()
-                .first_element_child()
-                .ok_or_else(|| rupnp::Error::ParseError("Browse Response contains no children"))?
-                .children()
-                .filter(roxmltree::Node::is_element)
-                .map(Content::from_xml)
-                .collect()
+            .extract("AssignedObjectID")
+    }
+
+    /// Appends a track to the end of a Sonos playlist.
+    /// The uri and metadata have to be escaped like for [queue_many](struct.Speaker.html#method.queue_many).
+    pub async fn add_to_playlist(&self, playlist_id: &str, uri: &str, metadata: &str) -> Result<()> {
+        let (_, update_id) = self.browse_metadata_with_update_id(playlist_id).await?;
+        let args = args! { "InstanceID": 0, "ObjectID": playlist_id, "UpdateID": update_id, "EnqueuedURI": uri, "EnqueuedURIMetaData": metadata, "AddAtIndex": u32::MAX };
+        self.action(AV_TRANSPORT, "AddURIToSavedQueue", args)
+            .await
+            .map(drop)
+    }
+
+    /// Moves the tracks at the indices in `tracks` to the matching indices in `new_positions`.
+    /// Indices start at 0. An empty `new_positions` removes the tracks from the playlist.
+    pub async fn reorder_playlist(
+        &self,
+        playlist_id: &str,
+        tracks: &[u32],
+        new_positions: &[u32],
+    ) -> Result<()> {
+        let join = |indices: &[u32]| {
+            indices
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let (_, update_id) = self.browse_metadata_with_update_id(playlist_id).await?;
+        let args = args! { "InstanceID": 0, "ObjectID": playlist_id, "UpdateID": update_id, "TrackList": join(tracks), "NewPositionList": join(new_positions) };
+        self.action(AV_TRANSPORT, "ReorderTracksInSavedQueue", args)
+            .await
+            .map(drop)
+    }
+
+    pub async fn rename_playlist(&self, playlist_id: &str, new_title: &str) -> Result<()> {
+        let (playlist, _) = self.browse_metadata_with_update_id(playlist_id).await?;
+        // The tags are XML themselves, so the title gets escaped once more along with them
+        let tag = |title: &str| {
+            let tag = format!("<dc:title>{}</dc:title>", escape_str_pcdata(title));
+            escape_str_pcdata(&tag).into_owned()
+        };
+        let args = args! { "ObjectID": playlist_id, "CurrentTagValue": tag(playlist.title()), "NewTagValue": tag(new_title) };
+        self.action(CONTENT_DIRECTORY, "UpdateObject", args)
+            .await
+            .map(drop)
+    }
+
+    pub async fn delete_playlist(&self, playlist_id: &str) -> Result<()> {
+        self.action(CONTENT_DIRECTORY, "DestroyObject", args! { "ObjectID": playlist_id })
+            .await
+            .map(drop)
+    }
+
+    /// Returns the metadata of an object along with its current update id, which edits have to
+    /// pass along.
+    async fn browse_metadata_with_update_id(&self, object_id: &str) -> Result<(Content, u32)> {
+        let args = args! { "ObjectID": object_id, "BrowseFlag": "BrowseMetadata", "StartingIndex": 0, "RequestedCount": 1, "Filter" : "", "SortCriteria" : "" };
+        let mut map = self.action(CONTENT_DIRECTORY, "Browse", args).await?;
+        let update_id = map
+            .extract("UpdateID")?
+            .parse()
+            .map_err(rupnp::Error::invalid_response)?;
+        let content = Content::from_didl(&map.extract("Result")?)?
+            .ok_or(rupnp::Error::ParseError("Browse Response contains no children"))?;
+
+        Ok((content, update_id))
     }
 
     /// Take a snapshot of the state the speaker is in right now.
@@ -550,26 +1256,76 @@ impl Speaker {
     }
 }
 
-pub(crate) fn extract_zone_topology(state_xml: &str) -> Result<Vec<(String, Vec<SpeakerInfo>)>> {
-    let doc = Document::parse(&state_xml)?;
-    let state = utils::find_root_node(&doc, "ZoneGroups", "Zone Group Topology")?;
+/// Sonos answers `GetEQ` and `SetEQ` with `402 Invalid Args` for settings the speaker doesn't have.
+fn unsupported_eq(err: Error, eq_type: EqType) -> Error {
+    match err {
+        Error::UPnP(rupnp::Error::UPnPError(ref e)) if e.err_code() == 402 => {
+            Error::Unsupported(eq_type.to_string())
+        }
+        err => err,
+    }
+}
 
-    state
-        .children()
-        .filter(Node::is_element)
-        .filter(|c| c.tag_name().name().eq_ignore_ascii_case("ZoneGroup"))
-        .map(|group| {
-            let coordinator = utils::find_node_attribute(group, "Coordinator")?.to_string();
-            let members = group
-                .children()
-                .filter(Node::is_element)
-                .filter(|c| c.tag_name().name().eq_ignore_ascii_case("ZoneGroupMember"))
-                .filter(|c| c.attribute("Invisible") != Some("1"))
-                .map(SpeakerInfo::from_xml)
-                .collect::<Result<Vec<_>>>()?;
-            Ok((coordinator, members))
-        })
-        .collect()
+/// Adds tracks to the queue in chunks of at most `MAX_URIS_PER_ENQUEUE`, calling `enqueue` with
+/// each chunk and the track number it should be inserted at. With a `position`, every chunk goes
+/// right after the previous one, otherwise all of them are appended (track number `0`).
+/// Returns `None` if there were no tracks.
+async fn enqueue_in_chunks<'a, F, Fut>(
+    tracks: &'a [(String, String)],
+    position: Option<u32>,
+    enqueue: F,
+) -> Result<Option<EnqueuedTracks>>
+where
+    F: Fn(&'a [(String, String)], u32) -> Fut,
+    Fut: Future<Output = Result<EnqueuedTracks>>,
+{
+    let mut enqueued: Option<EnqueuedTracks> = None;
+
+    for chunk in tracks.chunks(MAX_URIS_PER_ENQUEUE) {
+        let desired_track_no = match (position, &enqueued) {
+            (Some(_), Some(enqueued)) => enqueued.first_track_no + enqueued.num_tracks_added,
+            (position, _) => position.unwrap_or(0),
+        };
+        let added = enqueue(chunk, desired_track_no).await?;
+
+        enqueued = Some(match enqueued {
+            Some(enqueued) => EnqueuedTracks {
+                num_tracks_added: enqueued.num_tracks_added + added.num_tracks_added,
+                new_queue_length: added.new_queue_length,
+                ..enqueued
+            },
+            None => added,
+        });
+    }
+
+    Ok(enqueued)
+}
+
+/// Turns a function fetching the page at some index into a stream of the items of all pages.
+fn paged<'a, T, F, Fut>(fetch_page: F) -> impl Stream<Item = Result<T>> + 'a
+where
+    T: 'a,
+    F: Fn(u32) -> Fut + 'a,
+    Fut: Future<Output = Result<BrowseResult<T>>> + 'a,
+{
+    stream::try_unfold(Some(0), move |start: Option<u32>| {
+        let page = start.map(|start| (start, fetch_page(start)));
+        async move {
+            let (start, page) = match page {
+                Some(page) => page,
+                None => return Ok::<_, Error>(None),
+            };
+            let page = page.await?;
+            let next = start + page.number_returned();
+            let next = if page.number_returned() == 0 || next >= page.total_matches() {
+                None
+            } else {
+                Some(next)
+            };
+            Ok(Some((stream::iter(page.into_items().into_iter().map(Ok)), next)))
+        }
+    })
+    .try_flatten()
 }
 
 pub(crate) fn extract_av_transport_last_change(state_xml: &str) -> Result<Vec<(String, String)>> {
@@ -589,3 +1345,112 @@ pub(crate) fn extract_av_transport_last_change(state_xml: &str) -> Result<Vec<(S
         })
         .collect())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::future;
+    use std::cell::RefCell;
+
+    fn tracks(prefix: &str, count: usize) -> Vec<(String, String)> {
+        (0..count)
+            .map(|i| (format!("{}{}", prefix, i), String::new()))
+            .collect()
+    }
+
+    /// Enqueues into a fake queue the way Sonos does for `AddMultipleURIsToQueue`.
+    async fn enqueue_into(
+        queue: &RefCell<Vec<String>>,
+        tracks: &[(String, String)],
+        position: Option<u32>,
+    ) -> Result<Option<EnqueuedTracks>> {
+        enqueue_in_chunks(tracks, position, |chunk, desired_track_no| {
+            assert!(chunk.len() <= MAX_URIS_PER_ENQUEUE);
+            let mut queue = queue.borrow_mut();
+            let first_track_no = match desired_track_no {
+                0 => queue.len() as u32 + 1,
+                track_no => track_no,
+            };
+            for (i, (uri, _)) in chunk.iter().enumerate() {
+                queue.insert(first_track_no as usize - 1 + i, uri.clone());
+            }
+            let enqueued = EnqueuedTracks {
+                first_track_no,
+                num_tracks_added: chunk.len() as u32,
+                new_queue_length: queue.len() as u32,
+            };
+            async move { Ok(enqueued) }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_in_chunks() -> Result<()> {
+        let queue = RefCell::new(tracks("old", 5).into_iter().map(|(uri, _)| uri).collect());
+        let new = tracks("new", 17);
+
+        let enqueued = enqueue_into(&queue, &new, Some(3)).await?.unwrap();
+        assert_eq!(
+            enqueued,
+            EnqueuedTracks {
+                first_track_no: 3,
+                num_tracks_added: 17,
+                new_queue_length: 22,
+            }
+        );
+        let queue = queue.into_inner();
+        assert_eq!(&queue[..2], ["old0", "old1"]);
+        assert!(queue[2..19].iter().zip(&new).all(|(a, (b, _))| a == b));
+        assert_eq!(&queue[19..], ["old2", "old3", "old4"]);
+
+        let queue = RefCell::new(Vec::new());
+        let appended = enqueue_into(&queue, &tracks("new", 33), None).await?.unwrap();
+        assert_eq!(appended.first_track_no, 1);
+        assert_eq!(appended.num_tracks_added, 33);
+        assert_eq!(queue.into_inner().last().map(String::as_str), Some("new32"));
+
+        assert_eq!(enqueue_into(&RefCell::default(), &[], Some(1)).await?, None);
+        Ok(())
+    }
+
+    /// Pages through `count` items, `page_size` at a time, claiming `total_matches` results.
+    /// Returns the fetched items and the start index of every request.
+    async fn page_through(count: u32, page_size: u32, total_matches: u32) -> (Vec<u32>, Vec<u32>) {
+        let starts = RefCell::new(Vec::new());
+        let fetched = paged(|start| {
+            starts.borrow_mut().push(start);
+            let items: Vec<u32> = (start..count.min(start + page_size)).collect();
+            future::ready(Ok(BrowseResult {
+                number_returned: items.len() as u32,
+                items,
+                total_matches,
+                update_id: 1,
+            }))
+        })
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+        (fetched, starts.into_inner())
+    }
+
+    #[tokio::test]
+    async fn test_paged() {
+        let (items, starts) = page_through(7, 3, 7).await;
+        assert_eq!(items, (0..7).collect::<Vec<_>>());
+        assert_eq!(starts, [0, 3, 6]);
+
+        // no request for a page after the last full one
+        let (items, starts) = page_through(6, 3, 6).await;
+        assert_eq!(items.len(), 6);
+        assert_eq!(starts, [0, 3]);
+
+        // the speaker returning nothing ends the stream, even if it claimed more matches
+        let (items, starts) = page_through(4, 3, 10).await;
+        assert_eq!(items.len(), 4);
+        assert_eq!(starts, [0, 3, 4]);
+
+        let (items, starts) = page_through(0, 3, 0).await;
+        assert!(items.is_empty());
+        assert_eq!(starts, [0]);
+    }
+}
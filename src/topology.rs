@@ -0,0 +1,325 @@
+use crate::{utils, Error, Result, SpeakerInfo};
+use roxmltree::{Document, Node};
+use std::{fmt, str::FromStr};
+
+/// The topology of a Sonos system as reported by the `ZoneGroupTopology` service.
+/// Unlike the room view, it keeps the speakers that are hidden in bonded sets (the right speaker
+/// of a stereo pair, home theater surrounds and subs) as well as the devices that went missing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ZoneGroupState {
+    groups: Vec<ZoneGroup>,
+    vanished_devices: Vec<VanishedDevice>,
+}
+
+impl ZoneGroupState {
+    pub(crate) fn from_xml(state_xml: &str) -> Result<Self> {
+        let doc = Document::parse(state_xml)?;
+        let state = utils::find_root_node(&doc, "ZoneGroups", "Zone Group Topology")?;
+
+        let groups = child_elements(state, "ZoneGroup")
+            .map(ZoneGroup::from_xml)
+            .collect::<Result<_>>()?;
+        let vanished_devices = match doc
+            .descendants()
+            .find(|n| n.tag_name().name().eq_ignore_ascii_case("VanishedDevices"))
+        {
+            Some(vanished) => child_elements(vanished, "Device")
+                .map(VanishedDevice::from_xml)
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            groups,
+            vanished_devices,
+        })
+    }
+
+    /// All groups of the system.
+    pub fn groups(&self) -> &[ZoneGroup] {
+        &self.groups
+    }
+
+    /// Devices that were part of the system but are no longer reachable.
+    pub fn vanished_devices(&self) -> &[VanishedDevice] {
+        &self.vanished_devices
+    }
+
+    /// The group coordinators UUID along with the visible members of each group, i.e. one speaker per room.
+    pub(crate) fn rooms(&self) -> Vec<(String, Vec<SpeakerInfo>)> {
+        self.groups
+            .iter()
+            .map(|group| {
+                let members = group
+                    .members
+                    .iter()
+                    .filter(|member| !member.invisible)
+                    .map(|member| member.info.clone())
+                    .collect();
+                (group.coordinator.clone(), members)
+            })
+            .collect()
+    }
+}
+
+/// A group of rooms playing in sync, controlled through its coordinator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoneGroup {
+    id: String,
+    coordinator: String,
+    members: Vec<ZoneGroupMember>,
+}
+
+impl ZoneGroup {
+    fn from_xml(node: Node<'_, '_>) -> Result<Self> {
+        Ok(Self {
+            id: utils::find_node_attribute(node, "ID")?.to_string(),
+            coordinator: utils::find_node_attribute(node, "Coordinator")?.to_string(),
+            members: child_elements(node, "ZoneGroupMember")
+                .map(ZoneGroupMember::from_xml)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// The ID of the group, e.g. `RINCON_000E58A0B1C201400:2891`.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The UUID of the group coordinator.
+    pub fn coordinator(&self) -> &str {
+        &self.coordinator
+    }
+
+    /// All members of the group including the invisible ones.
+    pub fn members(&self) -> &[ZoneGroupMember] {
+        &self.members
+    }
+}
+
+/// A speaker in a zone group. Bonded speakers are members as well, but marked invisible (the right
+/// speaker of a stereo pair) or listed as satellites of their soundbar (home theater surrounds and subs).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoneGroupMember {
+    info: SpeakerInfo,
+    invisible: bool,
+    satellites: Vec<SpeakerInfo>,
+}
+
+impl ZoneGroupMember {
+    fn from_xml(node: Node<'_, '_>) -> Result<Self> {
+        Ok(Self {
+            info: SpeakerInfo::from_xml(node)?,
+            invisible: node.attribute("Invisible") == Some("1"),
+            satellites: child_elements(node, "Satellite")
+                .map(SpeakerInfo::from_xml)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// The name, UUID and location of the speaker.
+    pub fn info(&self) -> &SpeakerInfo {
+        &self.info
+    }
+
+    /// Whether the member is hidden from the room view because it is bonded to another speaker.
+    pub fn is_invisible(&self) -> bool {
+        self.invisible
+    }
+
+    /// The surrounds and subs bonded to this speaker.
+    pub fn satellites(&self) -> &[SpeakerInfo] {
+        &self.satellites
+    }
+}
+
+/// A device that dropped out of the system, e.g. because it was powered off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VanishedDevice {
+    uuid: String,
+    name: String,
+    reason: String,
+}
+
+impl VanishedDevice {
+    fn from_xml(node: Node<'_, '_>) -> Result<Self> {
+        Ok(Self {
+            uuid: utils::find_node_attribute(node, "UUID")?.to_string(),
+            name: utils::find_node_attribute(node, "ZoneName")?.to_string(),
+            reason: node.attribute("Reason").unwrap_or_default().to_string(),
+        })
+    }
+
+    /// The UUID of the device.
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    /// The room name the device belonged to.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Why the device vanished, e.g. `powered off`.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+fn child_elements<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(Node::is_element)
+        .filter(move |c| c.tag_name().name().eq_ignore_ascii_case(name))
+}
+
+/// Describes which audio channels the speakers of a bonded set play.
+/// It is parsed from the `ChannelMapSet` attribute of the zone group topology, which looks like
+/// `RINCON_000E58A0B1C201400:LF,LF;RINCON_000E58A0B1C301400:RF,RF` for a stereo pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelMap(Vec<(String, Vec<String>)>);
+
+impl ChannelMap {
+    /// The channel map of a stereo pair, where the left speaker plays the left front channel
+    /// and the right speaker the right front channel.
+    pub fn stereo_pair(left_uuid: &str, right_uuid: &str) -> Self {
+        ChannelMap(vec![
+            (
+                left_uuid.to_string(),
+                vec!["LF".to_string(), "LF".to_string()],
+            ),
+            (
+                right_uuid.to_string(),
+                vec!["RF".to_string(), "RF".to_string()],
+            ),
+        ])
+    }
+
+    /// Iterates over the UUIDs of the speakers in the map and their channels.
+    pub fn speakers(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.0
+            .iter()
+            .map(|(uuid, channels)| (uuid.as_str(), channels.as_slice()))
+    }
+
+    /// The channels played by the speaker with the given UUID, e.g. `["LF", "LF"]`.
+    pub fn channels(&self, uuid: &str) -> Option<&[String]> {
+        self.speakers()
+            .find(|(speaker, _)| speaker.eq_ignore_ascii_case(uuid))
+            .map(|(_, channels)| channels)
+    }
+}
+
+impl FromStr for ChannelMap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.split(';')
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (uuid, channels) = entry.split_once(':').ok_or(rupnp::Error::ParseError(
+                    "channel map entry without channels",
+                ))?;
+                Ok((
+                    uuid.to_string(),
+                    channels.split(',').map(str::to_string).collect(),
+                ))
+            })
+            .collect::<Result<_>>()
+            .map(ChannelMap)
+    }
+}
+
+impl fmt::Display for ChannelMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (uuid, channels)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(";")?;
+            }
+            write!(f, "{}:{}", uuid, channels.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEREO_PAIR: &str = r#"<ZoneGroups><ZoneGroup Coordinator="RINCON_B8E93781A1B201400" ID="RINCON_B8E93781A1B201400:2891"><ZoneGroupMember UUID="RINCON_B8E93781A1B201400" Location="http://192.168.1.21:1400/xml/device_description.xml" ZoneName="Office" ChannelMapSet="RINCON_B8E93781A1B201400:LF,LF;RINCON_B8E93781C3D401400:RF,RF"/><ZoneGroupMember UUID="RINCON_B8E93781C3D401400" Location="http://192.168.1.22:1400/xml/device_description.xml" ZoneName="Office" ChannelMapSet="RINCON_B8E93781A1B201400:LF,LF;RINCON_B8E93781C3D401400:RF,RF" Invisible="1"/></ZoneGroup></ZoneGroups>"#;
+
+    #[test]
+    fn test_channel_map_round_trip() -> Result<()> {
+        let map = ChannelMap::stereo_pair("RINCON_A", "RINCON_B");
+        assert_eq!(map.to_string(), "RINCON_A:LF,LF;RINCON_B:RF,RF");
+        assert_eq!(map.to_string().parse::<ChannelMap>()?, map);
+        assert_eq!(
+            map.channels("rincon_b"),
+            Some(&["RF".to_string(), "RF".to_string()][..])
+        );
+        assert!("RINCON_A".parse::<ChannelMap>().is_err());
+        Ok(())
+    }
+
+    const HOME_THEATER: &str = r#"<ZoneGroupState><ZoneGroups><ZoneGroup Coordinator="RINCON_542A1B5C6D7E01400" ID="RINCON_542A1B5C6D7E01400:1205"><ZoneGroupMember UUID="RINCON_542A1B5C6D7E01400" Location="http://192.168.1.30:1400/xml/device_description.xml" ZoneName="Living Room" HTSatChanMapSet="RINCON_542A1B5C6D7E01400:LF,RF;RINCON_7828CA1B2C3D01400:SW;RINCON_48A6B8C1D2E301400:LR;RINCON_48A6B8F4E5D601400:RR"><Satellite UUID="RINCON_7828CA1B2C3D01400" Location="http://192.168.1.31:1400/xml/device_description.xml" ZoneName="Living Room" HTSatChanMapSet="RINCON_542A1B5C6D7E01400:LF,RF;RINCON_7828CA1B2C3D01400:SW;RINCON_48A6B8C1D2E301400:LR;RINCON_48A6B8F4E5D601400:RR" Invisible="1"/><Satellite UUID="RINCON_48A6B8C1D2E301400" Location="http://192.168.1.32:1400/xml/device_description.xml" ZoneName="Living Room" HTSatChanMapSet="RINCON_542A1B5C6D7E01400:LF,RF;RINCON_7828CA1B2C3D01400:SW;RINCON_48A6B8C1D2E301400:LR;RINCON_48A6B8F4E5D601400:RR" Invisible="1"/><Satellite UUID="RINCON_48A6B8F4E5D601400" Location="http://192.168.1.33:1400/xml/device_description.xml" ZoneName="Living Room" HTSatChanMapSet="RINCON_542A1B5C6D7E01400:LF,RF;RINCON_7828CA1B2C3D01400:SW;RINCON_48A6B8C1D2E301400:LR;RINCON_48A6B8F4E5D601400:RR" Invisible="1"/></ZoneGroupMember></ZoneGroup></ZoneGroups><VanishedDevices><Device UUID="RINCON_B8E9378E9F0A01400" ZoneName="Bathroom" Reason="powered off" LastSeen="1634567890"/></VanishedDevices></ZoneGroupState>"#;
+
+    #[test]
+    fn test_stereo_pair_is_one_room() -> Result<()> {
+        let topology = ZoneGroupState::from_xml(STEREO_PAIR)?.rooms();
+        assert_eq!(topology.len(), 1);
+
+        let (_, members) = &topology[0];
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].name(), "Office");
+
+        let channel_map = members[0].channel_map().expect("is a stereo pair");
+        assert_eq!(channel_map.speakers().count(), 2);
+        assert_eq!(
+            channel_map.channels("RINCON_B8E93781C3D401400"),
+            Some(&["RF".to_string(), "RF".to_string()][..])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_home_theater_keeps_satellites() -> Result<()> {
+        let state = ZoneGroupState::from_xml(HOME_THEATER)?;
+        assert_eq!(state.rooms().len(), 1);
+        assert_eq!(state.rooms()[0].1.len(), 1);
+
+        let soundbar = &state.groups()[0].members()[0];
+        assert!(!soundbar.is_invisible());
+        assert_eq!(
+            soundbar.info().channels(),
+            Some(&["LF".to_string(), "RF".to_string()][..])
+        );
+
+        let roles: Vec<_> = soundbar
+            .satellites()
+            .iter()
+            .map(|satellite| satellite.channels().expect("is bonded").join(","))
+            .collect();
+        assert_eq!(roles, ["SW", "LR", "RR"]);
+
+        let vanished = &state.vanished_devices()[0];
+        assert_eq!(vanished.name(), "Bathroom");
+        assert_eq!(vanished.reason(), "powered off");
+        Ok(())
+    }
+
+    #[test]
+    fn test_stereo_pair_keeps_invisible_member() -> Result<()> {
+        let state = ZoneGroupState::from_xml(STEREO_PAIR)?;
+        let members = state.groups()[0].members();
+        assert_eq!(members.len(), 2);
+        assert!(members[1].is_invisible());
+        assert_eq!(
+            members[1].info().channels(),
+            Some(&["RF".to_string(), "RF".to_string()][..])
+        );
+        assert!(state.vanished_devices().is_empty());
+        Ok(())
+    }
+}
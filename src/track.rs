@@ -1,5 +1,9 @@
-use crate::{utils, Result};
+use crate::{
+    didl::{DidlLite, DidlObject, DidlResource},
+    utils, Result,
+};
 use roxmltree::Node;
+use std::time::Duration;
 
 /// A [Track](struct.Track.html) with some metadata like the track number, its duration and the
 /// elapsed time.
@@ -8,8 +12,10 @@ pub struct TrackInfo {
     track: Track,
     metadata: String,
     track_no: u32,
-    duration: u32,
-    elapsed: u32,
+    duration: Option<Duration>,
+    elapsed: Option<Duration>,
+    stream_content: Option<String>,
+    radio_show: Option<String>,
 }
 
 #[allow(missing_docs)]
@@ -18,8 +24,8 @@ impl TrackInfo {
         track: Track,
         metadata: String,
         track_no: u32,
-        duration: u32,
-        elapsed: u32,
+        duration: Option<Duration>,
+        elapsed: Option<Duration>,
     ) -> Self {
         Self {
             track,
@@ -27,6 +33,8 @@ impl TrackInfo {
             track_no,
             duration,
             elapsed,
+            stream_content: None,
+            radio_show: None,
         }
     }
 
@@ -39,12 +47,190 @@ impl TrackInfo {
     pub fn track_no(&self) -> u32 {
         self.track_no
     }
-    pub fn duration(&self) -> u32 {
+    /// `None` if the speaker doesn't know the duration, e.g. for streams.
+    pub fn duration(&self) -> Option<Duration> {
         self.duration
     }
-    pub fn elapsed(&self) -> u32 {
+    pub fn elapsed(&self) -> Option<Duration> {
         self.elapsed
     }
+    /// The album art of the track, see [Track::album_art_uri](struct.Track.html#method.album_art_uri).
+    pub fn album_art_uri(&self) -> Option<&str> {
+        self.track.album_art_uri()
+    }
+    /// What a radio station is currently playing, usually `Artist - Title`.
+    pub fn stream_content(&self) -> Option<&str> {
+        self.stream_content.as_deref()
+    }
+    /// The show currently on air on a radio station.
+    pub fn radio_show(&self) -> Option<&str> {
+        self.radio_show.as_deref()
+    }
+}
+
+/// The position of a speaker in its current track and in the whole media, as returned by
+/// [position_info](struct.Speaker.html#method.position_info).
+#[derive(Debug)]
+pub struct PositionInfo {
+    pub(crate) track_no: u32,
+    pub(crate) track_duration: Option<Duration>,
+    pub(crate) track_metadata: String,
+    pub(crate) track: Option<Track>,
+    pub(crate) track_uri: String,
+    pub(crate) rel_time: Option<Duration>,
+    pub(crate) abs_time: Option<Duration>,
+    pub(crate) rel_count: Option<i32>,
+    pub(crate) abs_count: Option<i32>,
+    pub(crate) stream_content: Option<String>,
+    pub(crate) radio_show: Option<String>,
+}
+
+#[allow(missing_docs)]
+impl PositionInfo {
+    pub(crate) fn from_map(map: &mut std::collections::HashMap<String, String>) -> Result<Self> {
+        use utils::HashMapExt;
+
+        let track_metadata = map.remove("TrackMetaData").unwrap_or_default();
+        let track_uri = map.remove("TrackURI").unwrap_or_default();
+        let item = match track_metadata.as_str() {
+            "" | "NOT_IMPLEMENTED" => None,
+            metadata => {
+                let didl = metadata.parse::<DidlLite>()?;
+                let item = didl.into_objects().into_iter().next().ok_or_else(|| {
+                    rupnp::Error::XmlMissingElement("Track Metadata".to_string(), "item".to_string())
+                })?;
+                Some(item)
+            }
+        };
+        let stream_content = item
+            .as_ref()
+            .and_then(|item| item.property("r:streamContent"))
+            .filter(|content| !content.is_empty() && !content.starts_with("ZPSTR_"))
+            .map(str::to_string);
+        let radio_show = item
+            .as_ref()
+            .and_then(|item| item.property("r:radioShowMd"))
+            .map(parse_radio_show)
+            .filter(|show| !show.is_empty())
+            .map(str::to_string);
+        let track = match item {
+            Some(mut item) => {
+                // Radio streams often come without a title or resource, fill those in so
+                // there still is a track
+                if item.resources().is_empty() {
+                    item.add_resource(DidlResource::new(track_uri.as_str()));
+                }
+                if matches!(item.title(), None | Some("")) {
+                    let title = stream_content.as_deref().unwrap_or(&track_uri).to_string();
+                    item.set_title(title);
+                }
+                Some(Track::from_object(&item)?)
+            }
+            None => None,
+        };
+        let duration = |value: Option<String>| match value {
+            Some(value) => utils::duration_from_str(&value),
+            None => Ok(None),
+        };
+
+        Ok(Self {
+            track_no: map
+                .extract("Track")?
+                .parse()
+                .map_err(rupnp::Error::invalid_response)?,
+            track_duration: duration(map.remove("TrackDuration"))?,
+            track_metadata,
+            track,
+            track_uri,
+            rel_time: duration(map.remove("RelTime"))?,
+            abs_time: duration(map.remove("AbsTime"))?,
+            rel_count: map.remove("RelCount").and_then(|x| x.parse().ok()),
+            abs_count: map.remove("AbsCount").and_then(|x| x.parse().ok()),
+            stream_content,
+            radio_show,
+        })
+    }
+
+    /// The number of the track in the queue, starting at `1`. `0` if there is no track.
+    pub fn track_no(&self) -> u32 {
+        self.track_no
+    }
+    pub fn track_duration(&self) -> Option<Duration> {
+        self.track_duration
+    }
+    pub fn track_metadata(&self) -> &str {
+        &self.track_metadata
+    }
+    /// The current track, parsed from the track metadata.
+    pub fn track(&self) -> Option<&Track> {
+        self.track.as_ref()
+    }
+    pub fn track_uri(&self) -> &str {
+        &self.track_uri
+    }
+    /// The elapsed time in the current track.
+    pub fn rel_time(&self) -> Option<Duration> {
+        self.rel_time
+    }
+    /// The elapsed time in the whole media. Sonos speakers mostly report `NOT_IMPLEMENTED`.
+    pub fn abs_time(&self) -> Option<Duration> {
+        self.abs_time
+    }
+    pub fn rel_count(&self) -> Option<i32> {
+        self.rel_count
+    }
+    pub fn abs_count(&self) -> Option<i32> {
+        self.abs_count
+    }
+    /// What a radio station is currently playing, from `r:streamContent`.
+    pub fn stream_content(&self) -> Option<&str> {
+        self.stream_content.as_deref()
+    }
+    /// The show on air on a radio station, from `r:radioShowMd`.
+    pub fn radio_show(&self) -> Option<&str> {
+        self.radio_show.as_deref()
+    }
+
+    /// Turns the position into a [TrackInfo](struct.TrackInfo.html), or `None` if there is no
+    /// current track.
+    pub fn into_track_info(self) -> Option<TrackInfo> {
+        let track = self.track?;
+        let mut info = TrackInfo::new(
+            track,
+            self.track_metadata,
+            self.track_no,
+            self.track_duration,
+            self.rel_time,
+        );
+        info.stream_content = self.stream_content;
+        info.radio_show = self.radio_show;
+        Some(info)
+    }
+}
+
+/// What is known about a URL passed to [play_url](struct.Speaker.html#method.play_url).
+/// Everything is optional, the metadata shown by Sonos is generated from whatever is given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackHints {
+    /// The title, defaults to the file name or URL.
+    pub title: Option<String>,
+    /// The artist, shown as the creator.
+    pub artist: Option<String>,
+    /// The album.
+    pub album: Option<String>,
+    /// The URL of the album art.
+    pub art: Option<String>,
+    /// The MIME type, e.g. `audio/flac`. Helps to tell streams from files when the URL has no
+    /// file extension.
+    pub mime: Option<String>,
+}
+
+/// `r:radioShowMd` looks like `Morning Show,p239255`, where the part after the comma is an id.
+fn parse_radio_show(show: &str) -> &str {
+    match show.rsplit_once(',') {
+        Some((name, id)) if id.starts_with('p') => name,
+        _ => show,
+    }
 }
 
 /// The track struct contains information about the music in UPnP music players.
@@ -55,8 +241,9 @@ pub struct Track {
     title: String,
     creator: Option<String>,
     album: Option<String>,
-    duration: Option<u32>,
+    duration: Option<Duration>,
     uri: String,
+    album_art_uri: Option<String>,
 }
 
 #[allow(missing_docs)]
@@ -70,12 +257,17 @@ impl Track {
     pub fn album(&self) -> Option<&str> {
         self.album.as_deref()
     }
-    pub fn duration(&self) -> Option<u32> {
+    pub fn duration(&self) -> Option<Duration> {
         self.duration
     }
     pub fn uri(&self) -> &str {
         &self.uri
     }
+    /// The URL of the album art. Tracks returned by a [Speaker](struct.Speaker.html) have
+    /// speaker-relative `/getaa?...` paths already resolved to absolute URLs.
+    pub fn album_art_uri(&self) -> Option<&str> {
+        self.album_art_uri.as_deref()
+    }
 }
 
 impl std::fmt::Display for Track {
@@ -93,35 +285,26 @@ impl std::fmt::Display for Track {
 
 impl Track {
     pub(crate) fn from_xml(node: Node<'_, '_>) -> Result<Self> {
-        let mut title = None;
-        let mut creator = None;
-        let mut album = None;
-        let mut res = None;
-
-        for child in node.children() {
-            match child.tag_name().name() {
-                "title" => title = Some(child.text().unwrap_or_default().to_string()),
-                "creator" => creator = Some(child.text().unwrap_or_default().to_string()),
-                "album" => album = Some(child.text().unwrap_or_default().to_string()),
-                "res" => res = Some(child),
-                _ => (),
-            }
-        }
+        Self::from_object(&DidlObject::from_xml(node)?)
+    }
 
-        let title = title.ok_or_else(|| {
-            rupnp::Error::XmlMissingElement(node.tag_name().name().to_string(), "title".to_string())
+    pub(crate) fn from_object(object: &DidlObject) -> Result<Self> {
+        let title = object.title().map(str::to_string).ok_or_else(|| {
+            rupnp::Error::XmlMissingElement(object.id().to_string(), "title".to_string())
         })?;
-        let res = res.ok_or_else(|| {
-            rupnp::Error::XmlMissingElement(node.tag_name().name().to_string(), "res".to_string())
+        let res = object.resources().first().ok_or_else(|| {
+            rupnp::Error::XmlMissingElement(object.id().to_string(), "res".to_string())
         })?;
         let duration = res
-            .attributes()
-            .iter()
-            .find(|a| a.name().eq_ignore_ascii_case("duration"))
-            .map(|a| utils::seconds_from_str(a.value()))
-            .transpose()?;
+            .duration()
+            .map(utils::duration_from_str)
+            .transpose()?
+            .flatten();
 
-        let uri = res.text().unwrap_or_default().to_string();
+        let uri = res.uri().to_string();
+        let creator = object.creator().map(str::to_string);
+        let album = object.album().map(str::to_string);
+        let album_art_uri = object.album_art_uri().map(str::to_string);
 
         Ok(Self {
             title,
@@ -129,6 +312,48 @@ impl Track {
             album,
             duration,
             uri,
+            album_art_uri,
         })
     }
+
+    pub(crate) fn resolve_album_art(mut self, base: &http::Uri) -> Self {
+        self.album_art_uri = self
+            .album_art_uri
+            .map(|uri| utils::resolve_uri(base, &uri));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_radio_position_info() -> Result<()> {
+        let metadata = r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns:r="urn:schemas-rinconnetworks-com:metadata-1-0/" xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"><item id="-1" parentID="-1" restricted="true"><res protocolInfo="sonos.com-http:*:application/octet-stream:*">x-sonosapi-stream:s17077?sid=254&amp;flags=8224&amp;sn=0</res><r:streamContent>Nina Simone - Feeling Good</r:streamContent><r:radioShowMd>Late Junction,p239255</r:radioShowMd><upnp:albumArtURI>/getaa?s=1&amp;u=x-sonosapi-stream%3as17077</upnp:albumArtURI><dc:title></dc:title><upnp:class>object.item</upnp:class></item></DIDL-Lite>"#;
+        let mut map: HashMap<String, String> = vec![
+            ("Track", "1"),
+            ("TrackDuration", "0:00:00"),
+            ("TrackMetaData", metadata),
+            ("TrackURI", "x-sonosapi-stream:s17077?sid=254&flags=8224&sn=0"),
+            ("RelTime", "0:12:01"),
+            ("AbsTime", "NOT_IMPLEMENTED"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let info = PositionInfo::from_map(&mut map)?
+            .into_track_info()
+            .expect("radio has a track");
+        assert_eq!(info.stream_content(), Some("Nina Simone - Feeling Good"));
+        assert_eq!(info.radio_show(), Some("Late Junction"));
+        assert_eq!(info.track().title(), "Nina Simone - Feeling Good");
+        assert_eq!(
+            info.track().uri(),
+            "x-sonosapi-stream:s17077?sid=254&flags=8224&sn=0"
+        );
+        Ok(())
+    }
 }
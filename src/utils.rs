@@ -1,5 +1,6 @@
 use crate::Result;
 use roxmltree::{Attribute, Document, Node};
+use std::time::Duration;
 
 #[doc(hidden)]
 #[macro_export]
@@ -36,17 +37,68 @@ pub fn seconds_to_str(seconds_total: i64) -> String {
 
     return format!("{}{:02}:{:02}:{:02}", sign, hours, minutes, seconds);
 }
-pub fn seconds_from_str(s: &str) -> Result<u32> {
+/// Parses durations like `1:02:03` or `0:03:21.000`. The fractional part is ignored.
+/// Empty strings and `NOT_IMPLEMENTED`, which speakers report e.g. for streams, yield `None`.
+pub fn duration_from_str(s: &str) -> Result<Option<Duration>> {
+    let s = s.trim();
+    if s.is_empty() || s.eq_ignore_ascii_case("NOT_IMPLEMENTED") {
+        return Ok(None);
+    }
+
     let opt = (|| {
         let mut split = s.splitn(3, ':');
-        let hours = split.next()?.parse::<u32>().ok()?;
-        let minutes = split.next()?.parse::<u32>().ok()?;
-        let seconds = split.next()?.parse::<u32>().ok()?;
+        let hours = split.next()?.parse::<u64>().ok()?;
+        let minutes = split.next()?.parse::<u64>().ok()?;
+        let seconds = split.next()?;
+        let seconds = seconds
+            .split_once('.')
+            .map_or(seconds, |(seconds, _)| seconds)
+            .parse::<u64>()
+            .ok()?;
 
-        Some(hours * 3600 + minutes * 60 + seconds)
+        Some(Duration::from_secs(hours * 3600 + minutes * 60 + seconds))
     })();
 
-    opt.ok_or(rupnp::Error::ParseError("invalid duration").into())
+    opt.map(Some)
+        .ok_or_else(|| rupnp::Error::ParseError("invalid duration").into())
+}
+pub fn seconds_from_str(s: &str) -> Result<u32> {
+    duration_from_str(s)?
+        .map(|duration| duration.as_secs() as u32)
+        .ok_or_else(|| rupnp::Error::ParseError("invalid duration").into())
+}
+
+/// Resolves a URI like the `/getaa?...` album art paths speakers report against the URL of the
+/// speaker. Absolute URIs are returned unchanged.
+pub fn resolve_uri(base: &http::Uri, reference: &str) -> String {
+    if matches!(reference.parse::<http::Uri>(), Ok(uri) if uri.scheme().is_some()) {
+        return reference.to_string();
+    }
+    let scheme = base.scheme_str().unwrap_or("http");
+    let authority = base.authority().map_or("", |authority| authority.as_str());
+    format!("{}://{}/{}", scheme, authority, reference.trim_start_matches('/'))
+}
+
+const LINE_IN_SCHEME: &str = "x-rincon-stream:";
+const TV_SCHEME: &str = "x-sonos-htastream:";
+
+/// The transport URI of the line-in of the speaker with the given UUID.
+pub fn line_in_uri(uuid: &str) -> String {
+    format!("{}{}", LINE_IN_SCHEME, uuid)
+}
+
+/// The transport URI of the TV input of the soundbar with the given UUID.
+pub fn tv_uri(uuid: &str) -> String {
+    format!("{}{}:spdif", TV_SCHEME, uuid)
+}
+
+pub fn is_tv_uri(uri: &str) -> bool {
+    uri.starts_with(TV_SCHEME)
+}
+
+/// Whether the URI is a line-in or TV input, which are live streams without tracks.
+pub fn is_input_stream_uri(uri: &str) -> bool {
+    uri.starts_with(LINE_IN_SCHEME) || is_tv_uri(uri)
 }
 
 pub fn parse_bool(s: String) -> Result<bool> {
@@ -57,6 +109,14 @@ pub fn parse_bool(s: String) -> Result<bool> {
     }
 }
 
+pub fn parse_on_off(s: String) -> Result<bool> {
+    match s.trim() {
+        "Off" => Ok(false),
+        "On" => Ok(true),
+        _ => Err(rupnp::Error::ParseError("state was neither `On` nor `Off`").into()),
+    }
+}
+
 pub fn find_node_attribute<'n, 'd: 'n>(node: Node<'d, 'n>, attr: &str) -> Result<&'n str> {
     node.attributes()
         .iter()
@@ -81,3 +141,51 @@ pub fn find_root_node<'a, 'input: 'a>(
             rupnp::Error::XmlMissingElement(docname.to_string(), element.to_string()).into()
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_from_str() -> Result<()> {
+        assert_eq!(duration_from_str("1:02:03")?, Some(Duration::from_secs(3723)));
+        assert_eq!(duration_from_str("0:03:21.000")?, Some(Duration::from_secs(201)));
+        assert_eq!(duration_from_str("NOT_IMPLEMENTED")?, None);
+        assert_eq!(duration_from_str("")?, None);
+        assert!(duration_from_str("3:21").is_err());
+        assert!(seconds_from_str("NOT_IMPLEMENTED").is_err());
+        assert_eq!(seconds_from_str("0:00:42")?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_uri() {
+        let base = "http://192.168.1.20:1400/xml/device_description.xml"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            resolve_uri(&base, "/getaa?s=1&u=x-file-cifs%3a%2f%2fnas%2fa.mp3"),
+            "http://192.168.1.20:1400/getaa?s=1&u=x-file-cifs%3a%2f%2fnas%2fa.mp3"
+        );
+        assert_eq!(
+            resolve_uri(&base, "https://i.scdn.co/image/ab67616d0000b273"),
+            "https://i.scdn.co/image/ab67616d0000b273"
+        );
+    }
+
+    #[test]
+    fn test_input_stream_uri() {
+        let line_in = line_in_uri("RINCON_000E5880EA7601400");
+        assert_eq!(line_in, "x-rincon-stream:RINCON_000E5880EA7601400");
+        assert!(is_input_stream_uri(&line_in));
+        assert!(!is_tv_uri(&line_in));
+
+        let tv = tv_uri("RINCON_48A6B8E8C3E601400");
+        assert_eq!(tv, "x-sonos-htastream:RINCON_48A6B8E8C3E601400:spdif");
+        assert!(is_input_stream_uri(&tv));
+        assert!(is_tv_uri(&tv));
+
+        assert!(!is_input_stream_uri("x-rincon-queue:RINCON_000E5880EA7601400#0"));
+        assert!(!is_input_stream_uri("x-sonosapi-stream:s17077?sid=254&flags=8224&sn=0"));
+    }
+}